@@ -0,0 +1,16 @@
+use bevy::{audio::{GlobalVolume, Volume}, prelude::*};
+use crate::settings::Settings;
+
+/// Applies the audio settings
+pub struct AudioSettingsPlugin;
+
+impl Plugin for AudioSettingsPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Update, apply_audio_settings.run_if(resource_changed::<Settings>));
+	}
+}
+
+/// Sets the global volume, sounds that are already playing keep their volume
+fn apply_audio_settings(settings: Res<Settings>, mut global_volume: ResMut<GlobalVolume>) {
+	global_volume.volume = Volume::new(settings.audio.master_volume.clamp(0.0, 1.0));
+}
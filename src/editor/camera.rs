@@ -0,0 +1,40 @@
+use bevy::{input::mouse::MouseMotion, prelude::*};
+use crate::input::{Action, ActionState};
+
+/// Units per second the editor camera flies at, sprinting goes faster
+const FLY_SPEED: f32 = 20.0;
+const FAST_MULTIPLIER: f32 = 4.0;
+/// Degrees the camera turns per pixel of mouse movement
+const LOOK_SENSITIVITY: f32 = 0.2;
+
+/// Flies the camera while the fly camera action is held, the mouse turns it, the movement actions move it
+/// and Jump and Crouch go up and down
+pub fn fly_camera(
+	time: Res<Time>,
+	actions: Res<ActionState>,
+	mut motion: EventReader<MouseMotion>,
+	mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+	let look: Vec2 = motion.read().map(|event| event.delta).sum();
+
+	if !actions.pressed(Action::EditorLook) {
+		return;
+	}
+
+	let Ok(mut transform) = camera_query.get_single_mut() else {
+		return;
+	};
+
+	let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+	let yaw = yaw - (look.x * LOOK_SENSITIVITY).to_radians();
+	let pitch = (pitch - (look.y * LOOK_SENSITIVITY).to_radians()).clamp(-89f32.to_radians(), 89f32.to_radians());
+	transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+
+	// Analog bindings keep their strength so a half tilted stick flies at half speed
+	let direction = *transform.forward() * actions.movement.x
+		+ *transform.right() * actions.movement.y
+		+ Vec3::Y * (actions.value(Action::Jump) - actions.value(Action::Crouch));
+
+	let speed = if actions.pressed(Action::Sprint) { FLY_SPEED * FAST_MULTIPLIER } else { FLY_SPEED };
+	transform.translation += direction.clamp_length_max(1.0) * speed * time.delta_secs();
+}
@@ -0,0 +1,196 @@
+use bevy::{math::bounding::{Aabb3d, RayCast3d}, prelude::*, window::PrimaryWindow};
+use crate::level::{LevelEntity, LevelObject, LevelShape};
+use super::{EditorState, EditorTool};
+
+/// Handles are this fraction of the distance to the camera long so they keep their size on screen
+const HANDLE_SCALE: f32 = 0.15;
+/// Radius of the grabbable tip of a handle, as a fraction of its length
+const HANDLE_RADIUS: f32 = 0.15;
+/// Degrees an object turns per pixel dragged with the rotate tool
+const ROTATE_DEGREES_PER_PIXEL: f32 = 0.5;
+/// Objects can't be scaled below this
+const MIN_SCALE: f32 = 0.05;
+/// Planes are picked as boxes this thick
+const PLANE_PICK_THICKNESS: f32 = 0.05;
+
+const SELECTION_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+const AXIS_COLORS: [Color; 3] = [
+	Color::srgb(0.9, 0.2, 0.2),
+	Color::srgb(0.2, 0.9, 0.2),
+	Color::srgb(0.2, 0.4, 0.95),
+];
+
+/// A gizmo handle held with the mouse
+pub struct Drag {
+	axis: usize,
+	last_cursor: Vec2,
+}
+
+/// Directions of the handles, moving and rotating use the world axes and scaling the object's own axes
+fn handle_axes(tool: EditorTool, transform: &Transform) -> [Vec3; 3] {
+	match tool {
+		EditorTool::Move | EditorTool::Rotate => [Vec3::X, Vec3::Y, Vec3::Z],
+		EditorTool::Scale => [*transform.right(), *transform.up(), *transform.back()],
+	}
+}
+
+fn handle_length(camera: &GlobalTransform, center: Vec3) -> f32 {
+	(camera.translation().distance(center) * HANDLE_SCALE).max(0.1)
+}
+
+/// Half size of the box around a shape before scaling
+fn shape_half_size(shape: LevelShape) -> Vec3 {
+	match shape {
+		LevelShape::Cuboid { size } => size / 2.0,
+		LevelShape::Plane { size } => Vec3::new(size.x / 2.0, PLANE_PICK_THICKNESS, size.y / 2.0),
+	}
+}
+
+/// Distance along a ray to an object's box, tested in the object's space so rotation and scale are respected
+fn ray_hit(ray: Ray3d, object: &LevelObject, transform: &GlobalTransform) -> Option<f32> {
+	let inverse = transform.affine().inverse();
+	let direction = inverse.transform_vector3(*ray.direction);
+	let length = direction.length();
+	let local_ray = Ray3d::new(inverse.transform_point3(ray.origin), Dir3::new(direction).ok()?);
+
+	RayCast3d::from_ray(local_ray, f32::MAX)
+		.aabb_intersection_at(&Aabb3d::new(Vec3::ZERO, shape_half_size(object.shape)))
+		.map(|distance| distance / length)
+}
+
+/// Distance between a ray and a point
+fn ray_point_distance(ray: Ray3d, point: Vec3) -> f32 {
+	let offset = point - ray.origin;
+	(offset - *ray.direction * offset.dot(*ray.direction).max(0.0)).length()
+}
+
+/// Clicking picks an object or grabs a handle of the selected one, dragging a handle moves, rotates or scales along its axis
+pub fn pick_and_drag(
+	mouse: Res<ButtonInput<MouseButton>>,
+	window_query: Query<&Window, With<PrimaryWindow>>,
+	camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+	interactions: Query<&Interaction>,
+	mut editor: ResMut<EditorState>,
+	mut objects: Query<(Entity, &LevelObject, &GlobalTransform, &mut Transform), With<LevelEntity>>,
+	mut drag: Local<Option<Drag>>,
+) {
+	// The selection goes away when the level reloads
+	if editor.selected.is_some_and(|entity| !objects.contains(entity)) {
+		editor.selected = None;
+	}
+
+	if !mouse.pressed(MouseButton::Left) || editor.selected.is_none() {
+		*drag = None;
+	}
+
+	let Ok(window) = window_query.get_single() else {
+		return;
+	};
+	let Some(cursor) = window.cursor_position() else {
+		return;
+	};
+	let Ok((camera, camera_transform)) = camera_query.get_single() else {
+		return;
+	};
+
+	if let (Some(held), Some(selected)) = (drag.as_mut(), editor.selected) {
+		if let Ok((_, _, _, mut transform)) = objects.get_mut(selected) {
+			let axis = handle_axes(editor.tool, &transform)[held.axis];
+			let center = transform.translation;
+			let length = handle_length(camera_transform, center);
+
+			// How far the cursor moved along the handle on screen
+			if let (Ok(start), Ok(end)) = (camera.world_to_viewport(camera_transform, center), camera.world_to_viewport(camera_transform, center + axis * length)) {
+				let screen_axis = end - start;
+
+				if screen_axis.length() >= 1.0 {
+					let pixels = (cursor - held.last_cursor).dot(screen_axis.normalize());
+					let amount = pixels / screen_axis.length() * length;
+
+					match editor.tool {
+						EditorTool::Move => transform.translation += axis * amount,
+						EditorTool::Rotate => transform.rotate(Quat::from_axis_angle(axis, (pixels * ROTATE_DEGREES_PER_PIXEL).to_radians())),
+						EditorTool::Scale => transform.scale[held.axis] = (transform.scale[held.axis] * (1.0 + amount / length)).max(MIN_SCALE),
+					}
+				}
+			}
+		}
+
+		held.last_cursor = cursor;
+		return;
+	}
+
+	if !mouse.just_pressed(MouseButton::Left) {
+		return;
+	}
+
+	// Clicks on the editor panel aren't for the level
+	if interactions.iter().any(|interaction| *interaction != Interaction::None) {
+		return;
+	}
+
+	let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+		return;
+	};
+
+	if let Some(selected) = editor.selected {
+		if let Ok((_, _, _, transform)) = objects.get(selected) {
+			let center = transform.translation;
+			let length = handle_length(camera_transform, center);
+
+			let grabbed = handle_axes(editor.tool, transform).iter()
+				.position(|axis| ray_point_distance(ray, center + *axis * length) <= length * HANDLE_RADIUS);
+
+			if let Some(axis) = grabbed {
+				*drag = Some(Drag { axis, last_cursor: cursor });
+				return;
+			}
+		}
+	}
+
+	editor.selected = objects.iter()
+		.filter_map(|(entity, object, transform, _)| ray_hit(ray, object, transform).map(|distance| (entity, distance)))
+		.min_by(|a, b| a.1.total_cmp(&b.1))
+		.map(|(entity, _)| entity);
+}
+
+/// Outlines the selected object and draws the handles of the current tool
+pub fn draw_gizmos(
+	mut gizmos: Gizmos,
+	editor: Res<EditorState>,
+	camera_query: Query<&GlobalTransform, With<Camera3d>>,
+	objects: Query<(&LevelObject, &Transform), With<LevelEntity>>,
+) {
+	let Some((object, transform)) = editor.selected.and_then(|entity| objects.get(entity).ok()) else {
+		return;
+	};
+	let Ok(camera_transform) = camera_query.get_single() else {
+		return;
+	};
+
+	let outline = transform.with_scale(transform.scale * shape_half_size(object.shape) * 2.0);
+	gizmos.cuboid(outline, SELECTION_COLOR);
+
+	let center = transform.translation;
+	let length = handle_length(camera_transform, center);
+
+	for (axis, color) in handle_axes(editor.tool, transform).into_iter().zip(AXIS_COLORS) {
+		let tip = center + axis * length;
+
+		match editor.tool {
+			EditorTool::Move => {
+				gizmos.arrow(center, tip, color);
+			},
+			EditorTool::Rotate => {
+				gizmos.circle(Isometry3d::new(center, Quat::from_rotation_arc(Vec3::Z, axis)), length, color);
+				gizmos.line(center, tip, color);
+			},
+			EditorTool::Scale => {
+				gizmos.line(center, tip, color);
+				gizmos.cuboid(Transform::from_translation(tip).with_rotation(transform.rotation).with_scale(Vec3::splat(length * HANDLE_RADIUS)), color);
+			},
+		}
+
+		gizmos.sphere(Isometry3d::from_translation(tip), length * HANDLE_RADIUS, color);
+	}
+}
@@ -0,0 +1,203 @@
+use std::fs;
+use bevy::{asset::io::file::FileAssetReader, log, prelude::*};
+use ron::ser::PrettyConfig;
+use crate::input::{Action, ActionState};
+use crate::level::{self, CurrentLevel, LevelAsset, LevelEntity, LevelObject, LevelShape, LevelTransform};
+use crate::state::GameState;
+
+mod camera;
+mod gizmo;
+
+/// Distance in front of the camera new objects are placed at
+const SPAWN_DISTANCE: f32 = 10.0;
+
+/// Level editor toggled from the game, objects are picked and changed with gizmos and saved back to the level file
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<EditorState>()
+		.add_event::<EditorCommand>()
+		.add_systems(Update, toggle_editor.run_if(in_state(GameState::InGame).or(in_state(GameState::Editor))))
+		.add_systems(OnExit(GameState::Editor), clear_selection)
+		.add_systems(Update, (
+			shortcuts,
+			run_commands,
+			camera::fly_camera,
+			gizmo::pick_and_drag,
+			gizmo::draw_gizmos,
+		).chain().run_if(in_state(GameState::Editor)));
+	}
+}
+
+/// What the gizmo of the selected object does
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum EditorTool {
+	#[default]
+	Move,
+	Rotate,
+	Scale,
+}
+
+impl EditorTool {
+	/// Name shown in the editor panel
+	pub fn name(self) -> &'static str {
+		match self {
+			EditorTool::Move => "Move",
+			EditorTool::Rotate => "Rotate",
+			EditorTool::Scale => "Scale",
+		}
+	}
+}
+
+/// The tool and the selected level object
+#[derive(Resource, Default)]
+pub struct EditorState {
+	pub tool: EditorTool,
+	pub selected: Option<Entity>,
+}
+
+/// Things the editor panel and the shortcuts ask the editor to do
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub enum EditorCommand {
+	Spawn(LevelShape),
+	Delete,
+	Save,
+}
+
+/// Switches between playing and editing
+fn toggle_editor(mut actions: ResMut<ActionState>, state: Res<State<GameState>>, mut next_state: ResMut<NextState<GameState>>) {
+	if !actions.just_pressed(Action::ToggleEditor) {
+		return;
+	}
+
+	match state.get() {
+		GameState::InGame => next_state.set(GameState::Editor),
+		GameState::Editor => next_state.set(GameState::InGame),
+		_ => return,
+	}
+
+	actions.consume(Action::ToggleEditor);
+}
+
+fn clear_selection(mut editor: ResMut<EditorState>) {
+	editor.selected = None;
+}
+
+/// Shortcuts from the editor actions, the tool actions pick the tool, Delete removes the selection,
+/// the modifier with Save saves and the main menu action deselects
+fn shortcuts(mut actions: ResMut<ActionState>, mut editor: ResMut<EditorState>, mut commands: EventWriter<EditorCommand>) {
+	if actions.just_pressed(Action::EditorMoveTool) {
+		editor.tool = EditorTool::Move;
+	}
+	if actions.just_pressed(Action::EditorRotateTool) {
+		editor.tool = EditorTool::Rotate;
+	}
+	if actions.just_pressed(Action::EditorScaleTool) {
+		editor.tool = EditorTool::Scale;
+	}
+
+	if actions.just_pressed(Action::MainMenu) && editor.selected.is_some() {
+		editor.selected = None;
+		actions.consume(Action::MainMenu);
+	}
+
+	if actions.just_pressed(Action::EditorDelete) {
+		commands.send(EditorCommand::Delete);
+	}
+
+	if actions.pressed(Action::EditorModifier) && actions.just_pressed(Action::EditorSave) {
+		commands.send(EditorCommand::Save);
+	}
+}
+
+/// Spawns, deletes and saves
+fn run_commands(
+	mut commands: Commands,
+	mut events: EventReader<EditorCommand>,
+	mut editor: ResMut<EditorState>,
+	camera_query: Query<&GlobalTransform, With<Camera3d>>,
+	objects: Query<(Entity, &LevelObject, &Transform, &MeshMaterial3d<StandardMaterial>), With<LevelEntity>>,
+	current: Option<Res<CurrentLevel>>,
+	levels: Res<Assets<LevelAsset>>,
+	mut meshes: ResMut<Assets<Mesh>>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	for event in events.read() {
+		match *event {
+			EditorCommand::Spawn(shape) => {
+				let Ok(camera) = camera_query.get_single() else {
+					continue;
+				};
+
+				let name = match shape {
+					LevelShape::Cuboid { .. } => "Cuboid",
+					LevelShape::Plane { .. } => "Plane",
+				};
+				let object = LevelObject {
+					name: name.to_string(),
+					shape,
+					transform: LevelTransform {
+						translation: camera.translation() + camera.forward() * SPAWN_DISTANCE,
+						..default()
+					},
+					// After everything else in the file
+					index: objects.iter().map(|(_, object, ..)| object.index + 1).max().unwrap_or(0),
+					..default()
+				};
+
+				editor.selected = Some(level::spawn_object(&mut commands, &object, &mut meshes, &mut materials));
+			},
+			EditorCommand::Delete => {
+				if let Some(entity) = editor.selected.take() {
+					commands.entity(entity).despawn_recursive();
+				}
+			},
+			EditorCommand::Save => {
+				let Some(current) = current.as_ref() else {
+					log::error!("Editor: no level is loaded");
+					continue;
+				};
+
+				// Objects keep their place in the file, added ones go at the end in the order they were added
+				let mut sorted: Vec<_> = objects.iter().collect();
+				sorted.sort_by_key(|(_, object, ..)| object.index);
+
+				let mut level = levels.get(&current.handle).cloned().unwrap_or_default();
+				level.objects = sorted.into_iter().map(|(_, object, transform, material)| {
+					let (color, roughness) = materials.get(&material.0)
+						.map_or((object.color, object.roughness), |material| {
+							let color = material.base_color.to_srgba();
+							([color.red, color.green, color.blue], material.perceptual_roughness)
+						});
+
+					LevelObject {
+						transform: LevelTransform::from(*transform),
+						color,
+						roughness,
+						..object.clone()
+					}
+				}).collect();
+
+				save_level(&current.path, &level);
+			},
+		}
+	}
+}
+
+/// Writes a level to its file in the assets folder, with `hot_reload` the file watcher then reloads it
+fn save_level(path: &str, level: &LevelAsset) {
+	let text = match ron::ser::to_string_pretty(level, PrettyConfig::default().indentor("\t".to_string())) {
+		Ok(text) => text,
+		Err(e) => {
+			log::error!("Editor: could not serialize the level: {}", e);
+			return;
+		}
+	};
+
+	let file = FileAssetReader::get_base_path().join("assets").join(path);
+	match fs::write(&file, text) {
+		Ok(()) => log::info!("Editor: saved {}", file.display()),
+		Err(e) => log::error!("Editor: could not write {}: {}", file.display(), e),
+	}
+}
@@ -0,0 +1,288 @@
+use bevy::{input::InputSystem, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+use crate::settings::Settings;
+
+/// Maps keyboard, mouse and gamepad bindings onto game actions
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<InputMap>()
+		.init_resource::<ActionState>()
+		.add_systems(PreUpdate, update_actions.after(InputSystem))
+		.add_systems(Update, apply_binding_settings.run_if(resource_changed::<Settings>));
+	}
+}
+
+/// Everything the player can do with an input
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+	MoveForward,
+	MoveBack,
+	MoveLeft,
+	MoveRight,
+	Sprint,
+	Jump,
+	Crouch,
+	Prone,
+	MainMenu,
+	ToggleView,
+	FreeLook,
+	Inventory,
+	ToggleEditor,
+	/// Held to turn and fly the editor camera, flying uses the movement, Jump and Crouch actions
+	EditorLook,
+	EditorMoveTool,
+	EditorRotateTool,
+	EditorScaleTool,
+	EditorDelete,
+	/// Held with Save so it isn't pressed by accident
+	EditorModifier,
+	EditorSave,
+}
+
+impl Action {
+	pub const ALL: [Action; 20] = [
+		Action::MoveForward,
+		Action::MoveBack,
+		Action::MoveLeft,
+		Action::MoveRight,
+		Action::Sprint,
+		Action::Jump,
+		Action::Crouch,
+		Action::Prone,
+		Action::MainMenu,
+		Action::ToggleView,
+		Action::FreeLook,
+		Action::Inventory,
+		Action::ToggleEditor,
+		Action::EditorLook,
+		Action::EditorMoveTool,
+		Action::EditorRotateTool,
+		Action::EditorScaleTool,
+		Action::EditorDelete,
+		Action::EditorModifier,
+		Action::EditorSave,
+	];
+
+	/// Name shown in menus
+	pub fn name(self) -> &'static str {
+		match self {
+			Action::MoveForward => "Move Forward",
+			Action::MoveBack => "Move Back",
+			Action::MoveLeft => "Move Left",
+			Action::MoveRight => "Move Right",
+			Action::Sprint => "Sprint",
+			Action::Jump => "Jump",
+			Action::Crouch => "Crouch",
+			Action::Prone => "Prone",
+			Action::MainMenu => "Main Menu",
+			Action::ToggleView => "Toggle View",
+			Action::FreeLook => "Free Look",
+			Action::Inventory => "Inventory",
+			Action::ToggleEditor => "Level Editor",
+			Action::EditorLook => "Fly Camera",
+			Action::EditorMoveTool => "Move Tool",
+			Action::EditorRotateTool => "Rotate Tool",
+			Action::EditorScaleTool => "Scale Tool",
+			Action::EditorDelete => "Delete Object",
+			Action::EditorModifier => "Save Modifier",
+			Action::EditorSave => "Save Level",
+		}
+	}
+
+	/// Only used in the level editor
+	pub fn is_editor(self) -> bool {
+		matches!(self,
+			Action::EditorLook | Action::EditorMoveTool | Action::EditorRotateTool | Action::EditorScaleTool
+			| Action::EditorDelete | Action::EditorModifier | Action::EditorSave)
+	}
+}
+
+/// A physical input that can trigger an action
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Binding {
+	Key(KeyCode),
+	Mouse(MouseButton),
+	GamepadButton(GamepadButton),
+	/// One direction of a gamepad axis, `positive` selects the side of the axis
+	GamepadAxis { axis: GamepadAxis, positive: bool },
+}
+
+impl Binding {
+	pub fn is_gamepad(&self) -> bool {
+		matches!(self, Binding::GamepadButton(_) | Binding::GamepadAxis { .. })
+	}
+
+	/// Name shown in menus
+	pub fn name(&self) -> String {
+		match self {
+			Binding::Key(key) => format!("{:?}", key).trim_start_matches("Key").to_string(),
+			Binding::Mouse(button) => format!("Mouse {:?}", button),
+			Binding::GamepadButton(button) => format!("{:?}", button),
+			Binding::GamepadAxis { axis, positive } => format!("{:?} {}", axis, if *positive { "+" } else { "-" }),
+		}
+	}
+}
+
+/// The bindings of every action, an action can have several bindings
+#[derive(Resource, Clone)]
+pub struct InputMap {
+	pub bindings: HashMap<Action, Vec<Binding>>,
+	/// Analog value above which an axis binding counts as pressed
+	pub press_threshold: f32,
+	/// Stick deflection ignored around the center
+	pub stick_deadzone: f32,
+	/// Exponent applied to the right stick deflection, higher values give finer aim near the center
+	pub look_response_curve: f32,
+}
+
+impl Default for InputMap {
+	fn default() -> Self {
+		let bindings = HashMap::from_iter([
+			(Action::MoveForward, vec![Binding::Key(KeyCode::KeyW), Binding::GamepadAxis { axis: GamepadAxis::LeftStickY, positive: true }]),
+			(Action::MoveBack, vec![Binding::Key(KeyCode::KeyS), Binding::GamepadAxis { axis: GamepadAxis::LeftStickY, positive: false }]),
+			(Action::MoveLeft, vec![Binding::Key(KeyCode::KeyA), Binding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: false }]),
+			(Action::MoveRight, vec![Binding::Key(KeyCode::KeyD), Binding::GamepadAxis { axis: GamepadAxis::LeftStickX, positive: true }]),
+			(Action::Sprint, vec![Binding::Key(KeyCode::ShiftLeft), Binding::GamepadButton(GamepadButton::LeftThumb), Binding::GamepadButton(GamepadButton::LeftTrigger2)]),
+			(Action::Jump, vec![Binding::Key(KeyCode::Space), Binding::GamepadButton(GamepadButton::South)]),
+			(Action::Crouch, vec![Binding::Key(KeyCode::KeyC), Binding::GamepadButton(GamepadButton::East)]),
+			(Action::Prone, vec![Binding::Key(KeyCode::KeyZ), Binding::GamepadButton(GamepadButton::DPadDown)]),
+			(Action::MainMenu, vec![Binding::Key(KeyCode::Escape), Binding::GamepadButton(GamepadButton::Start)]),
+			(Action::ToggleView, vec![Binding::Key(KeyCode::ArrowUp), Binding::GamepadButton(GamepadButton::North)]),
+			(Action::FreeLook, vec![Binding::Key(KeyCode::AltLeft), Binding::GamepadButton(GamepadButton::RightThumb)]),
+			(Action::Inventory, vec![Binding::Key(KeyCode::Tab), Binding::GamepadButton(GamepadButton::Select)]),
+			(Action::ToggleEditor, vec![Binding::Key(KeyCode::F1)]),
+			(Action::EditorLook, vec![Binding::Mouse(MouseButton::Right)]),
+			(Action::EditorMoveTool, vec![Binding::Key(KeyCode::Digit1)]),
+			(Action::EditorRotateTool, vec![Binding::Key(KeyCode::Digit2)]),
+			(Action::EditorScaleTool, vec![Binding::Key(KeyCode::Digit3)]),
+			(Action::EditorDelete, vec![Binding::Key(KeyCode::Delete)]),
+			(Action::EditorModifier, vec![Binding::Key(KeyCode::ControlLeft), Binding::Key(KeyCode::ControlRight)]),
+			(Action::EditorSave, vec![Binding::Key(KeyCode::KeyS)]),
+		]);
+
+		Self {
+			bindings,
+			press_threshold: 0.5,
+			stick_deadzone: 0.15,
+			look_response_curve: 2.0,
+		}
+	}
+}
+
+impl InputMap {
+	/// The bindings of an action
+	pub fn get(&self, action: Action) -> &[Binding] {
+		self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+	}
+}
+
+#[derive(Clone, Copy, Default)]
+struct ActionData {
+	value: f32,
+	pressed: bool,
+	just_pressed: bool,
+}
+
+/// The state of every action this frame, read this instead of the raw inputs
+#[derive(Resource, Default)]
+pub struct ActionState {
+	actions: HashMap<Action, ActionData>,
+	/// Analog movement, x is forward and y is right, its length is at most 1
+	pub movement: Vec2,
+	/// Right stick camera look after the deadzone and response curve, up is positive y
+	pub look: Vec2,
+}
+
+impl ActionState {
+	pub fn pressed(&self, action: Action) -> bool {
+		self.actions.get(&action).is_some_and(|data| data.pressed)
+	}
+
+	pub fn just_pressed(&self, action: Action) -> bool {
+		self.actions.get(&action).is_some_and(|data| data.just_pressed)
+	}
+
+	/// Strength of the action between 0 and 1, analog bindings give values in between
+	pub fn value(&self, action: Action) -> f32 {
+		self.actions.get(&action).map_or(0.0, |data| data.value)
+	}
+
+	/// Clears the just pressed flag so no other system reacts to it this frame
+	pub fn consume(&mut self, action: Action) {
+		if let Some(data) = self.actions.get_mut(&action) {
+			data.just_pressed = false;
+		}
+	}
+}
+
+/// Applies the bindings from the settings on top of the defaults
+fn apply_binding_settings(settings: Res<Settings>, mut map: ResMut<InputMap>) {
+	let mut bindings = InputMap::default().bindings;
+	bindings.extend(settings.controls.bindings.clone());
+	map.bindings = bindings;
+	map.stick_deadzone = settings.controls.stick_deadzone.clamp(0.0, 0.95);
+	map.look_response_curve = settings.controls.look_response_curve.max(0.1);
+}
+
+/// Reads every binding and updates the action state
+fn update_actions(
+	map: Res<InputMap>,
+	keys: Res<ButtonInput<KeyCode>>,
+	mouse: Res<ButtonInput<MouseButton>>,
+	gamepads: Query<&Gamepad>,
+	mut state: ResMut<ActionState>,
+) {
+	for action in Action::ALL {
+		let value = map.get(action).iter()
+			.map(|binding| binding_value(binding, &keys, &mouse, &gamepads))
+			.fold(0.0, f32::max);
+		let pressed = value >= map.press_threshold;
+
+		let data = state.actions.entry(action).or_default();
+		data.just_pressed = pressed && !data.pressed;
+		data.pressed = pressed;
+		data.value = value;
+	}
+
+	let movement = Vec2::new(
+		state.value(Action::MoveForward) - state.value(Action::MoveBack),
+		state.value(Action::MoveRight) - state.value(Action::MoveLeft),
+	);
+	state.movement = shape_stick(movement, map.stick_deadzone, 1.0);
+
+	let look = gamepads.iter()
+		.map(|gamepad| gamepad.right_stick())
+		.max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+		.unwrap_or_default();
+	state.look = shape_stick(look, map.stick_deadzone, map.look_response_curve);
+}
+
+/// Applies a radial deadzone and a response curve to a stick, keeping the result within the unit circle
+pub fn shape_stick(stick: Vec2, deadzone: f32, exponent: f32) -> Vec2 {
+	let stick = stick.clamp_length_max(1.0);
+	let length = stick.length();
+
+	if length <= deadzone {
+		return Vec2::ZERO;
+	}
+
+	let scaled = ((length - deadzone) / (1.0 - deadzone)).powf(exponent);
+	stick / length * scaled
+}
+
+/// Value of a single binding between 0 and 1, the strongest gamepad wins
+fn binding_value(binding: &Binding, keys: &ButtonInput<KeyCode>, mouse: &ButtonInput<MouseButton>, gamepads: &Query<&Gamepad>) -> f32 {
+	match *binding {
+		Binding::Key(key) => if keys.pressed(key) { 1.0 } else { 0.0 },
+		Binding::Mouse(button) => if mouse.pressed(button) { 1.0 } else { 0.0 },
+		Binding::GamepadButton(button) => gamepads.iter()
+			.map(|gamepad| gamepad.get(button).unwrap_or(if gamepad.pressed(button) { 1.0 } else { 0.0 }))
+			.fold(0.0, f32::max),
+		Binding::GamepadAxis { axis, positive } => gamepads.iter()
+			.filter_map(|gamepad| gamepad.get(axis))
+			.map(|value| if positive { value } else { -value })
+			.fold(0.0, f32::max),
+	}
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use bevy::{asset::{io::Reader, AssetLoader, LoadContext}, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// A level described in a `.level.ron` file, everything in it is spawned when it loads and again when the file changes
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct LevelAsset {
+	pub objects: Vec<LevelObject>,
+	pub lights: Vec<LevelLight>,
+	pub spawn_points: Vec<LevelSpawnPoint>,
+}
+
+/// A primitive mesh with its material and an optional matching collider, spawned objects keep it as a component
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LevelObject {
+	pub name: String,
+	pub shape: LevelShape,
+	pub transform: LevelTransform,
+	/// Base color in sRGB
+	pub color: [f32; 3],
+	pub roughness: f32,
+	pub collider: bool,
+	/// Position in the file's object list, saving writes the objects back in this order
+	#[serde(skip)]
+	pub index: usize,
+}
+
+impl Default for LevelObject {
+	fn default() -> Self {
+		Self {
+			name: String::new(),
+			shape: LevelShape::Cuboid { size: Vec3::ONE },
+			transform: LevelTransform::default(),
+			color: [1.0, 1.0, 1.0],
+			roughness: 0.5,
+			collider: true,
+			index: 0,
+		}
+	}
+}
+
+/// The meshes a level object can be
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum LevelShape {
+	Cuboid { size: Vec3 },
+	/// A flat plane facing up
+	Plane { size: Vec2 },
+}
+
+/// Position, rotation in degrees around the x, y and z axes, and scale
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct LevelTransform {
+	pub translation: Vec3,
+	pub rotation: Vec3,
+	pub scale: Vec3,
+}
+
+impl Default for LevelTransform {
+	fn default() -> Self {
+		Self { translation: Vec3::ZERO, rotation: Vec3::ZERO, scale: Vec3::ONE }
+	}
+}
+
+impl From<LevelTransform> for Transform {
+	fn from(transform: LevelTransform) -> Self {
+		let rotation = transform.rotation * std::f32::consts::PI / 180.0;
+
+		Transform {
+			translation: transform.translation,
+			rotation: Quat::from_euler(EulerRot::YXZ, rotation.y, rotation.x, rotation.z),
+			scale: transform.scale,
+		}
+	}
+}
+
+impl From<Transform> for LevelTransform {
+	fn from(transform: Transform) -> Self {
+		let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::YXZ);
+
+		Self {
+			translation: transform.translation,
+			rotation: Vec3::new(pitch, yaw, roll) * 180.0 / std::f32::consts::PI,
+			scale: transform.scale,
+		}
+	}
+}
+
+/// A light placed in the level, shadows follow the graphics settings
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LevelLight {
+	pub kind: LevelLightKind,
+	pub transform: LevelTransform,
+	/// Color in sRGB
+	pub color: [f32; 3],
+}
+
+impl Default for LevelLight {
+	fn default() -> Self {
+		Self {
+			kind: LevelLightKind::Point { intensity: 1_000_000.0, range: 20.0 },
+			transform: LevelTransform::default(),
+			color: [1.0, 1.0, 1.0],
+		}
+	}
+}
+
+/// The kinds of lights, intensities are in lumens and illuminance in lux like Bevy's lights
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum LevelLightKind {
+	Point { intensity: f32, range: f32 },
+	/// Shines along the transform's forward direction, the angle is in degrees
+	Spot { intensity: f32, range: f32, angle: f32 },
+	/// Shines along the transform's forward direction
+	Directional { illuminance: f32 },
+}
+
+/// A spawn point placed in the level, the facing is a yaw in degrees where 0 looks down -Z
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct LevelSpawnPoint {
+	pub name: String,
+	pub position: Vec3,
+	pub facing: f32,
+	pub team: Option<String>,
+}
+
+/// Reads `.level.ron` files
+#[derive(Default)]
+pub struct LevelLoader;
+
+/// Why a level file couldn't be loaded
+#[derive(Debug)]
+pub enum LevelLoaderError {
+	Io(std::io::Error),
+	Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for LevelLoaderError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LevelLoaderError::Io(error) => write!(f, "could not read the level file: {}", error),
+			LevelLoaderError::Ron(error) => write!(f, "could not parse the level file: {}", error),
+		}
+	}
+}
+
+impl std::error::Error for LevelLoaderError {}
+
+impl AssetLoader for LevelLoader {
+	type Asset = LevelAsset;
+	type Settings = ();
+	type Error = LevelLoaderError;
+
+	async fn load(&self, reader: &mut dyn Reader, _settings: &(), _load_context: &mut LoadContext<'_>) -> Result<LevelAsset, LevelLoaderError> {
+		let mut bytes = Vec::new();
+		reader.read_to_end(&mut bytes).await.map_err(LevelLoaderError::Io)?;
+		ron::de::from_bytes(&bytes).map_err(LevelLoaderError::Ron)
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["level.ron"]
+	}
+}
@@ -1,33 +1,166 @@
-use bevy::prelude::*;
-use bevy_rapier3d::prelude::*;
+use bevy::{log, prelude::*};
+use crate::physics::{Collider, RigidBody};
+use crate::settings::Settings;
+
+mod asset;
+mod spawn;
+pub mod terrain;
+
+pub use asset::{LevelAsset, LevelLight, LevelLightKind, LevelObject, LevelShape, LevelSpawnPoint, LevelTransform};
+pub use spawn::{SpawnPoint, SpawnPoints, SpawnSelection};
+
+/// Level loaded at startup, relative to the assets folder
+const DEFAULT_LEVEL: &str = "levels/default.level.ron";
+
+/// Thickness of the collider under a plane
+const PLANE_COLLIDER_THICKNESS: f32 = 0.1;
 
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_systems(Startup, init_level);
+		app.add_plugins(terrain::TerrainPlugin)
+		.init_asset::<LevelAsset>()
+		.init_asset_loader::<asset::LevelLoader>()
+		.init_resource::<SpawnSelection>()
+		.add_systems(Startup, load_level)
+		.add_systems(Update, spawn_level);
 	}
 }
 
-fn init_level(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>) {
-	let level_material = materials.add(StandardMaterial {
-		base_color: Color::WHITE,
-		..default()
+/// The level file being played
+#[derive(Resource)]
+pub struct CurrentLevel {
+	/// Asset path of the level file
+	pub path: String,
+	pub handle: Handle<LevelAsset>,
+}
+
+/// Marks everything spawned from the level file, it is despawned when the file is reloaded
+#[derive(Component)]
+pub struct LevelEntity;
+
+fn load_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(CurrentLevel {
+		path: DEFAULT_LEVEL.to_string(),
+		handle: asset_server.load(DEFAULT_LEVEL),
+	});
+}
+
+/// Spawns the level once it loads and respawns it whenever the file changes on disk
+fn spawn_level(
+	mut commands: Commands,
+	mut events: EventReader<AssetEvent<LevelAsset>>,
+	current: Option<Res<CurrentLevel>>,
+	levels: Res<Assets<LevelAsset>>,
+	settings: Res<Settings>,
+	spawned: Query<Entity, With<LevelEntity>>,
+	mut meshes: ResMut<Assets<Mesh>>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	let Some(current) = current else {
+		return;
+	};
+
+	let changed = events.read().any(|event| match event {
+		AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => *id == current.handle.id(),
+		_ => false,
 	});
+	if !changed {
+		return;
+	}
+
+	let Some(level) = levels.get(&current.handle) else {
+		log::error!("Level: {} changed but isn't loaded", current.path);
+		return;
+	};
+
+	for entity in spawned.iter() {
+		commands.entity(entity).despawn_recursive();
+	}
+
+	for (index, object) in level.objects.iter().enumerate() {
+		spawn_object(&mut commands, &LevelObject { index, ..object.clone() }, &mut meshes, &mut materials);
+	}
+
+	for light in &level.lights {
+		spawn_light(&mut commands, light, settings.graphics.shadows);
+	}
+
+	for spawn_point in &level.spawn_points {
+		spawn_spawn_point(&mut commands, spawn_point);
+	}
+
+	log::info!("Level: spawned {}", current.path);
+}
+
+/// The mesh of a shape, and its collider before scaling
+pub fn shape_mesh(shape: LevelShape) -> (Mesh, Collider) {
+	match shape {
+		LevelShape::Cuboid { size } => (
+			Cuboid::from_size(size).into(),
+			Collider::cuboid(size.x / 2.0, size.y / 2.0, size.z / 2.0),
+		),
+		LevelShape::Plane { size } => (
+			Plane3d::new(Vec3::Y, size / 2.0).into(),
+			Collider::cuboid(size.x / 2.0, PLANE_COLLIDER_THICKNESS / 2.0, size.y / 2.0),
+		),
+	}
+}
+
+/// Spawns a level object and returns its entity
+pub fn spawn_object(commands: &mut Commands, object: &LevelObject, meshes: &mut Assets<Mesh>, materials: &mut Assets<StandardMaterial>) -> Entity {
+	let (mesh, collider) = shape_mesh(object.shape);
+	let [red, green, blue] = object.color;
 
-	// Ground
+	let mut entity = commands.spawn((
+		Name::new(object.name.clone()),
+		Mesh3d(meshes.add(mesh)),
+		MeshMaterial3d(materials.add(StandardMaterial {
+			base_color: Color::srgb(red, green, blue),
+			perceptual_roughness: object.roughness,
+			..default()
+		})),
+		Transform::from(object.transform),
+		object.clone(),
+		LevelEntity,
+	));
+
+	if object.collider {
+		entity.insert((collider, RigidBody::Fixed));
+	}
+
+	entity.id()
+}
+
+fn spawn_light(commands: &mut Commands, light: &LevelLight, shadows: bool) {
+	let [red, green, blue] = light.color;
+	let color = Color::srgb(red, green, blue);
+	let transform = Transform::from(light.transform);
+
+	match light.kind {
+		LevelLightKind::Point { intensity, range } => commands.spawn((
+			PointLight { color, intensity, range, shadows_enabled: shadows, ..default() },
+			transform,
+			LevelEntity,
+		)),
+		LevelLightKind::Spot { intensity, range, angle } => commands.spawn((
+			SpotLight { color, intensity, range, outer_angle: angle.to_radians(), inner_angle: angle.to_radians() * 0.8, shadows_enabled: shadows, ..default() },
+			transform,
+			LevelEntity,
+		)),
+		LevelLightKind::Directional { illuminance } => commands.spawn((
+			DirectionalLight { color, illuminance, shadows_enabled: shadows, ..default() },
+			transform,
+			LevelEntity,
+		)),
+	};
+}
+
+fn spawn_spawn_point(commands: &mut Commands, spawn_point: &LevelSpawnPoint) {
 	commands.spawn((
-        Collider::cuboid(1000., 0., 1000.),
-		Mesh3d(meshes.add(Plane3d::new(Vec3::Y, Vec2::splat(1000.)))),
-		MeshMaterial3d(level_material.clone()),
-		Transform::IDENTITY,
-    ));
-
-	// Target Wall
-    commands.spawn((
-        Collider::cuboid(30., 30., 30.),
-		Mesh3d(meshes.add(Cuboid::from_length(60.))),
-		MeshMaterial3d(level_material.clone()),
-		Transform::from_xyz(0., 0., -100.),
-    ));
-}
\ No newline at end of file
+		SpawnPoint { name: spawn_point.name.clone(), team: spawn_point.team.clone() },
+		Transform::from_translation(spawn_point.position).with_rotation(Quat::from_rotation_y(spawn_point.facing.to_radians())),
+		LevelEntity,
+	));
+}
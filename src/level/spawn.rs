@@ -0,0 +1,71 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use crate::physics::{Collider, QueryFilter, SpatialQuery};
+use super::terrain::{Terrain, TerrainChunk};
+
+/// Rays looking for the ground reach this far below a spawn point
+const RAYCAST_DEPTH: f32 = 500.0;
+
+/// Where the player can start and respawn, the transform gives the position and the facing
+#[derive(Component, Clone, Debug)]
+pub struct SpawnPoint {
+	pub name: String,
+	/// Only players of this team use the spawn point when one is set
+	pub team: Option<String>,
+}
+
+/// Which spawn point the player uses, by name first and then by team, the first spawn point is used otherwise
+#[derive(Resource, Default, Clone)]
+pub struct SpawnSelection {
+	pub name: Option<String>,
+	pub team: Option<String>,
+}
+
+/// The spawn points of the level and what's under them
+#[derive(SystemParam)]
+pub struct SpawnPoints<'w, 's> {
+	points: Query<'w, 's, (&'static SpawnPoint, &'static GlobalTransform)>,
+	selection: Res<'w, SpawnSelection>,
+	terrain: Option<Res<'w, Terrain>>,
+	/// Terrain chunks that have their collider
+	solid_chunks: Query<'w, 's, &'static TerrainChunk, With<Collider>>,
+	spatial_query: SpatialQuery<'w, 's>,
+}
+
+impl SpawnPoints<'_, '_> {
+	/// The spawn point picked in the selection
+	pub fn selected(&self) -> Option<(&SpawnPoint, &GlobalTransform)> {
+		let by_name = self.selection.name.as_ref()
+			.and_then(|name| self.points.iter().find(|(point, _)| &point.name == name));
+		let by_team = || self.selection.team.as_ref()
+			.and_then(|team| self.points.iter().find(|(point, _)| point.team.as_ref() == Some(team)));
+
+		by_name.or_else(by_team).or_else(|| self.points.iter().next())
+	}
+
+	/// Height of the ground under a position, `None` while it isn't known yet
+	///
+	/// Over the terrain this waits for the collider of the chunk underneath so the player can't fall through it,
+	/// the ground is then whatever a ray hits first, level geometry on top of the terrain included. The ray starts
+	/// `clearance` above the position so a roof over the spawn point isn't taken for the ground
+	pub fn ground_height(&self, position: Vec3, clearance: f32, filter: QueryFilter) -> Option<f32> {
+		let terrain = self.terrain.as_ref()
+			.filter(|terrain| terrain.contains(position.x, position.z));
+
+		if let Some(terrain) = terrain {
+			let coord = terrain.chunk_at(position.x, position.z)?;
+			if !self.solid_chunks.iter().any(|chunk| chunk.coord == coord) {
+				return None;
+			}
+		}
+
+		let origin = position + Vec3::Y * clearance;
+		let hit = self.spatial_query.raycast(origin, Dir3::NEG_Y, clearance + RAYCAST_DEPTH, filter).map(|point| point.y);
+		let terrain_height = terrain.map(|terrain| terrain.height_at(position.x, position.z));
+
+		// A spawn point buried in the terrain goes up to its surface
+		match (hit, terrain_height) {
+			(Some(hit), Some(terrain_height)) => Some(hit.max(terrain_height)),
+			(hit, terrain_height) => hit.or(terrain_height),
+		}
+	}
+}
@@ -0,0 +1,91 @@
+use bevy::{prelude::*, render::render_resource::TextureFormat};
+
+/// Normalized (0..1) height samples read from a grayscale image
+#[derive(Clone)]
+pub struct Heightmap {
+	pub width: u32,
+	pub height: u32,
+	samples: Vec<f32>,
+}
+
+impl Heightmap {
+	/// Reads the first channel of every pixel, returns None for formats we can't decode
+	pub fn from_image(image: &Image) -> Option<Self> {
+		let size = image.size();
+		let format = image.texture_descriptor.format;
+
+		let samples: Vec<f32> = match format {
+			TextureFormat::R16Uint | TextureFormat::R16Unorm => image.data.chunks_exact(2)
+				.map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / u16::MAX as f32)
+				.collect(),
+			TextureFormat::Rg16Uint | TextureFormat::Rg16Unorm => image.data.chunks_exact(4)
+				.map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / u16::MAX as f32)
+				.collect(),
+			TextureFormat::Rgba16Uint | TextureFormat::Rgba16Unorm => image.data.chunks_exact(8)
+				.map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / u16::MAX as f32)
+				.collect(),
+			TextureFormat::R8Unorm | TextureFormat::R8Uint => image.data.iter()
+				.map(|byte| *byte as f32 / u8::MAX as f32)
+				.collect(),
+			TextureFormat::Rg8Unorm | TextureFormat::Rg8Uint => image.data.chunks_exact(2)
+				.map(|bytes| bytes[0] as f32 / u8::MAX as f32)
+				.collect(),
+			TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Uint => image.data.chunks_exact(4)
+				.map(|bytes| bytes[0] as f32 / u8::MAX as f32)
+				.collect(),
+			TextureFormat::R32Float => image.data.chunks_exact(4)
+				.map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+				.collect(),
+			_ => return None,
+		};
+
+		if samples.len() != (size.x * size.y) as usize {
+			return None;
+		}
+
+		Some(Self { width: size.x, height: size.y, samples })
+	}
+
+	/// The sample at a pixel, clamped to the image edges
+	pub fn get(&self, x: i32, y: i32) -> f32 {
+		let x = x.clamp(0, self.width as i32 - 1) as usize;
+		let y = y.clamp(0, self.height as i32 - 1) as usize;
+		self.samples[y * self.width as usize + x]
+	}
+
+	/// Bilinear sample at fractional pixel coordinates
+	pub fn sample(&self, x: f32, y: f32) -> f32 {
+		let x0 = x.floor();
+		let y0 = y.floor();
+		let tx = x - x0;
+		let ty = y - y0;
+
+		let h00 = self.get(x0 as i32, y0 as i32);
+		let h10 = self.get(x0 as i32 + 1, y0 as i32);
+		let h01 = self.get(x0 as i32, y0 as i32 + 1);
+		let h11 = self.get(x0 as i32 + 1, y0 as i32 + 1);
+
+		let top = h00 + (h10 - h00) * tx;
+		let bottom = h01 + (h11 - h01) * tx;
+		top + (bottom - top) * ty
+	}
+
+	/// Levels every sample within `radius` pixels of `center` to the height there, easing back to the original heights
+	/// over the next `blend` pixels
+	pub fn flatten(&mut self, center: Vec2, radius: f32, blend: f32) {
+		let level = self.sample(center.x, center.y);
+		let blend = blend.max(f32::EPSILON);
+
+		for y in 0..self.height {
+			for x in 0..self.width {
+				let distance = Vec2::new(x as f32, y as f32).distance(center);
+				let t = ((distance - radius) / blend).clamp(0.0, 1.0);
+				// Smoothstep so the slope eases in on both sides
+				let weight = t * t * (3.0 - 2.0 * t);
+
+				let sample = &mut self.samples[(y * self.width + x) as usize];
+				*sample = level + (*sample - level) * weight;
+			}
+		}
+	}
+}
@@ -0,0 +1,112 @@
+use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
+
+use super::{mesh, streaming::{ChunkBuild, ChunkTask}, Terrain, TerrainChunk, TerrainSettings};
+
+/// Distance thresholds that pick the level of detail of the terrain chunks
+#[derive(Resource, Clone)]
+pub struct TerrainLod {
+	/// Camera distances at which a chunk switches to the next coarser level, in ascending order
+	pub thresholds: Vec<f32>,
+}
+
+impl Default for TerrainLod {
+	fn default() -> Self {
+		Self {
+			thresholds: vec![300.0, 600.0, 1000.0, 1600.0],
+		}
+	}
+}
+
+impl TerrainLod {
+	/// Level of detail for a distance, 0 being the full heightmap resolution
+	pub fn level_at(&self, distance: f32) -> u32 {
+		self.thresholds.iter().take_while(|threshold| distance >= **threshold).count() as u32
+	}
+}
+
+/// Level of detail a chunk mesh is built with, along with the levels of its neighbours to stitch the seams
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ChunkLod {
+	pub level: u32,
+	/// Levels of the -x, +x, -z and +z neighbours
+	pub neighbours: [u32; 4],
+}
+
+impl ChunkLod {
+	pub const WEST: usize = 0;
+	pub const EAST: usize = 1;
+	pub const NORTH: usize = 2;
+	pub const SOUTH: usize = 3;
+
+	/// Picks the level of a chunk and its neighbours from the camera position
+	pub fn new(terrain: &Terrain, lod: &TerrainLod, camera: Vec3, coord: UVec2) -> Self {
+		let coord = coord.as_ivec2();
+
+		Self {
+			level: chunk_level(terrain, lod, camera, coord),
+			neighbours: [IVec2::NEG_X, IVec2::X, IVec2::NEG_Y, IVec2::Y]
+				.map(|offset| chunk_level(terrain, lod, camera, coord + offset)),
+		}
+	}
+
+	/// Number of heightmap samples between two vertices at a level
+	pub fn step(level: u32) -> u32 {
+		1 << level
+	}
+}
+
+/// Level of a chunk, limited so the step always divides the chunk size
+fn chunk_level(terrain: &Terrain, lod: &TerrainLod, camera: Vec3, coord: IVec2) -> u32 {
+	let center = terrain.origin.xz() + (coord.as_vec2() + 0.5) * terrain.chunk_size();
+	lod.level_at(center.distance(camera.xz())).min(terrain.chunk_quads.trailing_zeros())
+}
+
+/// Position of the active 3D camera
+pub fn active_camera_position(cameras: &Query<(&Camera, &GlobalTransform), With<Camera3d>>) -> Option<Vec3> {
+	cameras.iter()
+		.find(|(camera, _)| camera.is_active)
+		.map(|(_, transform)| transform.translation())
+}
+
+/// Rebuilds the mesh of loaded chunks whose level of detail changed
+pub fn update_chunk_lods(
+	mut commands: Commands,
+	terrain: Res<Terrain>,
+	settings: Res<TerrainSettings>,
+	lod: Res<TerrainLod>,
+	cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+	mut chunks: Query<(Entity, &mut TerrainChunk), Without<ChunkTask>>,
+	tasks: Query<(), With<ChunkTask>>,
+) {
+	let Some(camera) = active_camera_position(&cameras) else {
+		return;
+	};
+
+	let mut budget = settings.max_chunk_tasks.saturating_sub(tasks.iter().count());
+	let pool = AsyncComputeTaskPool::get();
+
+	for (entity, mut chunk) in chunks.iter_mut() {
+		if budget == 0 {
+			return;
+		}
+
+		let chunk_lod = ChunkLod::new(&terrain, &lod, camera, chunk.coord);
+		if chunk_lod == chunk.lod {
+			continue;
+		}
+
+		chunk.lod = chunk_lod;
+		budget -= 1;
+
+		let coord = chunk.coord;
+		let task_terrain = terrain.clone();
+		let task = pool.spawn(async move {
+			ChunkBuild {
+				mesh: mesh::build_chunk_mesh(&task_terrain, coord, chunk_lod),
+				collider: None,
+			}
+		});
+
+		commands.entity(entity).insert(ChunkTask(task));
+	}
+}
@@ -0,0 +1,105 @@
+use bevy::{prelude::*, render::{mesh::{Indices, PrimitiveTopology}, render_asset::RenderAssetUsages}};
+use crate::physics::Collider;
+
+use super::{ChunkLod, Terrain};
+
+/// Builds the render mesh of a chunk at a level of detail, vertices are relative to the chunk center
+pub fn build_chunk_mesh(terrain: &Terrain, coord: UVec2, lod: ChunkLod) -> Mesh {
+	let step = ChunkLod::step(lod.level) as i32;
+	let quads = terrain.chunk_quads / step as u32;
+	let first = (coord * terrain.chunk_quads).as_ivec2();
+	let half = terrain.chunk_size() / 2.0;
+	let uv_scale = Vec2::new((terrain.heightmap.width - 1) as f32, (terrain.heightmap.height - 1) as f32);
+
+	let vertex_count = ((quads + 1) * (quads + 1)) as usize;
+	let mut positions = Vec::with_capacity(vertex_count);
+	let mut normals = Vec::with_capacity(vertex_count);
+	let mut uvs = Vec::with_capacity(vertex_count);
+
+	for z in 0..=quads as i32 {
+		for x in 0..=quads as i32 {
+			let local = IVec2::new(x, z) * step;
+			let pixel = first + local;
+			let height = vertex_height(terrain, first, local, &lod) * terrain.vertical_scale;
+
+			positions.push([local.x as f32 * terrain.spacing - half, height, local.y as f32 * terrain.spacing - half]);
+			normals.push(sample_normal(terrain, pixel).to_array());
+			uvs.push((pixel.as_vec2() / uv_scale).to_array());
+		}
+	}
+
+	let row = quads + 1;
+	let mut indices = Vec::with_capacity((quads * quads * 6) as usize);
+
+	for z in 0..quads {
+		for x in 0..quads {
+			let i = z * row + x;
+			indices.extend_from_slice(&[i, i + row, i + 1, i + 1, i + row, i + row + 1]);
+		}
+	}
+
+	Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+		.with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+		.with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+		.with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+		.with_inserted_indices(Indices::U32(indices))
+}
+
+/// Builds a heightfield collider that lines up with the chunk mesh
+pub fn build_chunk_collider(terrain: &Terrain, coord: UVec2) -> Collider {
+	let quads = terrain.chunk_quads;
+	let first = (coord * quads).as_ivec2();
+	let samples = (quads + 1) as usize;
+
+	// Rapier expects the heights column major with rows along z and columns along x
+	let mut heights = Vec::with_capacity(samples * samples);
+	for x in 0..=quads as i32 {
+		for z in 0..=quads as i32 {
+			heights.push(terrain.heightmap.get(first.x + x, first.y + z));
+		}
+	}
+
+	Collider::heightfield(heights, samples, samples, Vec3::new(terrain.chunk_size(), terrain.vertical_scale, terrain.chunk_size()))
+}
+
+/// Normal at a heightmap pixel using central differences, so neighbouring chunks share normals on their seams
+fn sample_normal(terrain: &Terrain, pixel: IVec2) -> Vec3 {
+	let scale = terrain.vertical_scale / (2.0 * terrain.spacing);
+	let dx = (terrain.heightmap.get(pixel.x + 1, pixel.y) - terrain.heightmap.get(pixel.x - 1, pixel.y)) * scale;
+	let dz = (terrain.heightmap.get(pixel.x, pixel.y + 1) - terrain.heightmap.get(pixel.x, pixel.y - 1)) * scale;
+
+	Vec3::new(-dx, 1.0, -dz).normalize()
+}
+
+/// Height of a vertex, vertices on an edge shared with a coarser neighbour are moved onto the neighbour's edge so the seam has no cracks
+fn vertex_height(terrain: &Terrain, first: IVec2, local: IVec2, lod: &ChunkLod) -> f32 {
+	let quads = terrain.chunk_quads as i32;
+	let edge = if local.x == 0 {
+		Some((ChunkLod::WEST, IVec2::Y))
+	} else if local.x == quads {
+		Some((ChunkLod::EAST, IVec2::Y))
+	} else if local.y == 0 {
+		Some((ChunkLod::NORTH, IVec2::X))
+	} else if local.y == quads {
+		Some((ChunkLod::SOUTH, IVec2::X))
+	} else {
+		None
+	};
+
+	if let Some((side, axis)) = edge {
+		let neighbour_step = ChunkLod::step(lod.neighbours[side]) as i32;
+		let along = local.dot(axis);
+		let offset = along % neighbour_step;
+
+		if lod.neighbours[side] > lod.level && offset != 0 {
+			let start = first + local - axis * offset;
+			let a = terrain.heightmap.get(start.x, start.y);
+			let end = start + axis * neighbour_step;
+			let b = terrain.heightmap.get(end.x, end.y);
+			return a + (b - a) * offset as f32 / neighbour_step as f32;
+		}
+	}
+
+	let pixel = first + local;
+	terrain.heightmap.get(pixel.x, pixel.y)
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+use bevy::{log, prelude::*};
+
+mod heightmap;
+mod lod;
+mod mesh;
+mod streaming;
+
+pub use heightmap::Heightmap;
+pub use lod::{ChunkLod, TerrainLod};
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<TerrainSettings>()
+		.init_resource::<TerrainLod>()
+		.init_resource::<streaming::LoadedChunks>()
+		.add_systems(Startup, load_heightmap)
+		.add_systems(Update, (
+			init_terrain.run_if(resource_exists::<HeightmapHandle>),
+			(streaming::stream_chunks, lod::update_chunk_lods, streaming::finish_chunk_tasks).chain().run_if(resource_exists::<Terrain>),
+		));
+	}
+}
+
+/// Tunables for the terrain generated from the heightmap
+#[derive(Resource, Clone)]
+pub struct TerrainSettings {
+	/// Asset path of the grayscale heightmap image
+	pub heightmap: String,
+	/// Width of the whole terrain in world units, the depth follows the image aspect ratio
+	pub world_size: f32,
+	/// World height of a fully white heightmap sample
+	pub vertical_scale: f32,
+	/// Number of quads along one side of a chunk
+	pub chunk_quads: u32,
+	/// Chunks within this many chunks of the player are spawned
+	pub load_radius: u32,
+	/// Chunks further than this many chunks from the player are despawned
+	pub unload_radius: u32,
+	/// Maximum number of chunk meshes building at the same time
+	pub max_chunk_tasks: usize,
+	/// The terrain is flat within this distance of the world origin, and that flat ground is at y 0 where levels are built
+	pub play_area_radius: f32,
+	/// Distance past the play area over which the terrain rises or falls back to the heightmap
+	pub play_area_blend: f32,
+}
+
+impl Default for TerrainSettings {
+	fn default() -> Self {
+		Self {
+			heightmap: "height map/New Mexico Height Map (SRTM3 v4.1).png".to_string(),
+			world_size: 4096.0,
+			vertical_scale: 300.0,
+			chunk_quads: 64,
+			load_radius: 4,
+			unload_radius: 5,
+			max_chunk_tasks: 8,
+			play_area_radius: 200.0,
+			play_area_blend: 300.0,
+		}
+	}
+}
+
+/// The loaded terrain, inserted once the heightmap image is ready
+#[derive(Resource, Clone)]
+pub struct Terrain {
+	pub heightmap: Arc<Heightmap>,
+	/// World distance between two heightmap samples
+	pub spacing: f32,
+	pub vertical_scale: f32,
+	pub chunk_quads: u32,
+	/// Number of chunks along x and z
+	pub chunks: UVec2,
+	/// World position of the first heightmap sample
+	pub origin: Vec3,
+}
+
+impl Terrain {
+	pub fn new(mut heightmap: Heightmap, settings: &TerrainSettings) -> Self {
+		let spacing = settings.world_size / (heightmap.width.max(2) - 1) as f32;
+		let size = Vec2::new((heightmap.width - 1) as f32, (heightmap.height - 1) as f32) * spacing;
+
+		// The world origin is in the middle of the heightmap
+		let center = size / 2.0 / spacing;
+		heightmap.flatten(center, settings.play_area_radius / spacing, settings.play_area_blend / spacing);
+		let ground = heightmap.sample(center.x, center.y) * settings.vertical_scale;
+		let chunk_quads = settings.chunk_quads.max(1);
+		let chunks = UVec2::new(
+			(heightmap.width - 1).div_ceil(chunk_quads),
+			(heightmap.height - 1).div_ceil(chunk_quads),
+		);
+
+		Self {
+			heightmap: Arc::new(heightmap),
+			spacing,
+			vertical_scale: settings.vertical_scale,
+			chunk_quads,
+			chunks,
+			origin: Vec3::new(-size.x / 2.0, -ground, -size.y / 2.0),
+		}
+	}
+
+	/// World size of one chunk side
+	pub fn chunk_size(&self) -> f32 {
+		self.chunk_quads as f32 * self.spacing
+	}
+
+	/// World position of a chunk center at ground level
+	pub fn chunk_center(&self, coord: UVec2) -> Vec3 {
+		let half = self.chunk_size() / 2.0;
+		self.origin + Vec3::new(coord.x as f32 * self.chunk_size() + half, 0.0, coord.y as f32 * self.chunk_size() + half)
+	}
+
+	/// Whether a world position is over the terrain
+	pub fn contains(&self, x: f32, z: f32) -> bool {
+		self.chunk_at(x, z).is_some()
+	}
+
+	/// The chunk under a world position, `None` past the edges
+	pub fn chunk_at(&self, x: f32, z: f32) -> Option<UVec2> {
+		let size = Vec2::new((self.heightmap.width - 1) as f32, (self.heightmap.height - 1) as f32) * self.spacing;
+		let local = Vec2::new(x, z) - self.origin.xz();
+		if local.x < 0.0 || local.y < 0.0 || local.x > size.x || local.y > size.y {
+			return None;
+		}
+
+		let coord = (local / self.chunk_size()).floor().as_uvec2();
+		Some(coord.min(self.chunks - UVec2::ONE))
+	}
+
+	/// Terrain height under a world position, positions past the edges get the height of the closest edge
+	pub fn height_at(&self, x: f32, z: f32) -> f32 {
+		let pixel = (Vec2::new(x, z) - self.origin.xz()) / self.spacing;
+		self.origin.y + self.heightmap.sample(pixel.x, pixel.y) * self.vertical_scale
+	}
+}
+
+/// Marks a spawned terrain chunk
+#[derive(Component)]
+pub struct TerrainChunk {
+	pub coord: UVec2,
+	/// Level of detail of the latest mesh build
+	pub lod: ChunkLod,
+}
+
+/// Heightmap image that is still loading
+#[derive(Resource)]
+struct HeightmapHandle(Handle<Image>);
+
+#[derive(Resource)]
+struct TerrainMaterial(Handle<StandardMaterial>);
+
+fn load_heightmap(mut commands: Commands, asset_server: Res<AssetServer>, settings: Res<TerrainSettings>, mut materials: ResMut<Assets<StandardMaterial>>) {
+	commands.insert_resource(HeightmapHandle(asset_server.load(&settings.heightmap)));
+	commands.insert_resource(TerrainMaterial(materials.add(StandardMaterial {
+		base_color: Color::srgb(0.72, 0.6, 0.45),
+		perceptual_roughness: 1.0,
+		..default()
+	})));
+}
+
+/// Creates the terrain resource once the heightmap image has loaded, chunks are then streamed in around the player
+fn init_terrain(
+	mut commands: Commands,
+	handle: Res<HeightmapHandle>,
+	images: Res<Assets<Image>>,
+	settings: Res<TerrainSettings>,
+) {
+	let Some(image) = images.get(&handle.0) else {
+		return;
+	};
+
+	commands.remove_resource::<HeightmapHandle>();
+
+	let Some(heightmap) = Heightmap::from_image(image) else {
+		log::error!("Terrain: unsupported heightmap format {:?}", image.texture_descriptor.format);
+		return;
+	};
+
+	let terrain = Terrain::new(heightmap, &settings);
+	log::info!("Terrain: loaded {}x{} chunks", terrain.chunks.x, terrain.chunks.y);
+	commands.insert_resource(terrain);
+}
@@ -0,0 +1,114 @@
+use bevy::{prelude::*, tasks::{block_on, poll_once, AsyncComputeTaskPool, Task}, utils::HashMap};
+use crate::physics::{Collider, RigidBody};
+
+use crate::player::controller::PlayerControllerState;
+use super::{lod::{self, ChunkLod, TerrainLod}, mesh, Terrain, TerrainChunk, TerrainMaterial, TerrainSettings};
+
+/// Chunks that currently have an entity, loaded or still building
+#[derive(Resource, Default)]
+pub struct LoadedChunks {
+	pub chunks: HashMap<UVec2, Entity>,
+}
+
+/// Output of a chunk build, the collider is only built when the chunk is first spawned
+pub struct ChunkBuild {
+	pub mesh: Mesh,
+	pub collider: Option<Collider>,
+}
+
+/// Chunk mesh being built on the async compute pool
+#[derive(Component)]
+pub struct ChunkTask(pub Task<ChunkBuild>);
+
+/// Spawns the chunks in range of the player and despawns the ones that left it
+pub fn stream_chunks(
+	mut commands: Commands,
+	terrain: Res<Terrain>,
+	settings: Res<TerrainSettings>,
+	lod: Res<TerrainLod>,
+	player: Res<PlayerControllerState>,
+	cameras: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+	mut loaded: ResMut<LoadedChunks>,
+	tasks: Query<(), With<ChunkTask>>,
+) {
+	let chunk_size = terrain.chunk_size();
+	let center = ((player.position.xz() - terrain.origin.xz()) / chunk_size).floor().as_ivec2();
+	let load_radius = settings.load_radius as i32;
+	let unload_radius = settings.unload_radius.max(settings.load_radius) as i32;
+
+	// Despawn chunks outside of the unload ring
+	loaded.chunks.retain(|coord, entity| {
+		let keep = coord.as_ivec2().distance_squared(center) <= unload_radius * unload_radius;
+		if !keep {
+			commands.entity(*entity).despawn_recursive();
+		}
+		keep
+	});
+
+	// Queue the missing chunks closest first, limited by the amount of builds in flight
+	let mut missing = Vec::new();
+	for x in center.x - load_radius..=center.x + load_radius {
+		for z in center.y - load_radius..=center.y + load_radius {
+			let coord = IVec2::new(x, z);
+
+			if coord.x < 0 || coord.y < 0 || coord.x >= terrain.chunks.x as i32 || coord.y >= terrain.chunks.y as i32 {
+				continue;
+			}
+
+			if coord.distance_squared(center) > load_radius * load_radius || loaded.chunks.contains_key(&coord.as_uvec2()) {
+				continue;
+			}
+
+			missing.push(coord);
+		}
+	}
+	missing.sort_by_key(|coord| coord.distance_squared(center));
+
+	let budget = settings.max_chunk_tasks.saturating_sub(tasks.iter().count());
+	let pool = AsyncComputeTaskPool::get();
+	let camera = lod::active_camera_position(&cameras).unwrap_or(player.position);
+
+	for coord in missing.into_iter().take(budget).map(|coord| coord.as_uvec2()) {
+		let chunk_lod = ChunkLod::new(&terrain, &lod, camera, coord);
+		let task_terrain = terrain.clone();
+		let task = pool.spawn(async move {
+			ChunkBuild {
+				mesh: mesh::build_chunk_mesh(&task_terrain, coord, chunk_lod),
+				collider: Some(mesh::build_chunk_collider(&task_terrain, coord)),
+			}
+		});
+
+		let entity = commands.spawn((
+			TerrainChunk { coord, lod: chunk_lod },
+			ChunkTask(task),
+			Transform::from_translation(terrain.chunk_center(coord)),
+			Visibility::Visible,
+		)).id();
+
+		loaded.chunks.insert(coord, entity);
+	}
+}
+
+/// Inserts the mesh, and the collider for new chunks, once a build has finished
+pub fn finish_chunk_tasks(
+	mut commands: Commands,
+	mut tasks: Query<(Entity, &mut ChunkTask)>,
+	material: Res<TerrainMaterial>,
+	mut meshes: ResMut<Assets<Mesh>>,
+) {
+	for (entity, mut task) in tasks.iter_mut() {
+		let Some(build) = block_on(poll_once(&mut task.0)) else {
+			continue;
+		};
+
+		let mut chunk = commands.entity(entity);
+		chunk.remove::<ChunkTask>().insert((
+			Mesh3d(meshes.add(build.mesh)),
+			MeshMaterial3d(material.0.clone()),
+		));
+
+		if let Some(collider) = build.collider {
+			chunk.insert((collider, RigidBody::Fixed));
+		}
+	}
+}
@@ -1,18 +1,40 @@
 use bevy::prelude::*;
+use crate::settings::Settings;
 
 pub struct LightPlugin;
 
 impl Plugin for LightPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_systems(Startup, spawn_light);
+		app.add_systems(Startup, spawn_light)
+		.add_systems(Update, apply_light_settings.run_if(resource_changed::<Settings>));
 	}
 }
 
-fn spawn_light(mut commands: Commands) {
+fn spawn_light(mut commands: Commands, settings: Res<Settings>) {
 	let light = (
-		DirectionalLight{illuminance: light_consts::lux::OVERCAST_DAY, shadows_enabled: true, ..default()},
+		DirectionalLight{illuminance: light_consts::lux::OVERCAST_DAY, shadows_enabled: settings.graphics.shadows, ..default()},
         Transform::from_xyz(100., 200., 100.).looking_at(Vec3::ZERO, Vec3::Y),
     );	
 
 	commands.spawn(light);
-}
\ No newline at end of file
+}
+
+/// Applies the graphics settings to the lights, including the ones placed by the level
+fn apply_light_settings(
+	settings: Res<Settings>,
+	mut directional_lights: Query<&mut DirectionalLight>,
+	mut point_lights: Query<&mut PointLight>,
+	mut spot_lights: Query<&mut SpotLight>,
+) {
+	for mut light in directional_lights.iter_mut() {
+		light.shadows_enabled = settings.graphics.shadows;
+	}
+
+	for mut light in point_lights.iter_mut() {
+		light.shadows_enabled = settings.graphics.shadows;
+	}
+
+	for mut light in spot_lights.iter_mut() {
+		light.shadows_enabled = settings.graphics.shadows;
+	}
+}
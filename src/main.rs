@@ -1,22 +1,36 @@
-use avian3d::PhysicsPlugins;
-use bevy::prelude::*;
-use bevy_rapier3d::plugin::{NoUserData, RapierPhysicsPlugin};
+use bevy::{asset::AssetMetaCheck, prelude::*};
 
+mod audio;
+mod editor;
+mod input;
 mod light;
+mod physics;
 mod player;
+mod settings;
+mod state;
+mod ui;
 mod utils;
 mod level;
 mod window;
 
 fn main() {
 	App::new().add_plugins((
-		DefaultPlugins,
-		RapierPhysicsPlugin::<NoUserData>::default(), 
-		PhysicsPlugins::default(),
+		// The bundled assets ship with Unity .meta files that Bevy can't parse
+		DefaultPlugins.set(AssetPlugin {
+			meta_check: AssetMetaCheck::Never,
+			..default()
+		}),
+		settings::SettingsPlugin,
+		state::GameStatePlugin,
+		physics::PhysicsPlugin,
+		input::ActionsPlugin,
 		player::PlayerPlugin, 
 		light::LightPlugin, 
+		audio::AudioSettingsPlugin,
 		level::LevelPlugin, 
+		editor::EditorPlugin,
 		utils::diagnostics::DebugMenuPlugin,
 		window::WindowSettingsPlugin,
+		ui::UiPlugin,
 	)).run();
 }
\ No newline at end of file
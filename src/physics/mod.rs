@@ -0,0 +1,75 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::prelude::*;
+use crate::state::GameState;
+
+pub use bevy_rapier3d::prelude::{
+	CharacterAutostep,
+	CharacterLength,
+	Collider,
+	KinematicCharacterController,
+	KinematicCharacterControllerOutput,
+	QueryFilter,
+	RigidBody,
+};
+
+/// Thin layer over Rapier so the rest of the game doesn't talk to the physics engine directly
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
+		.configure_sets(FixedUpdate, PhysicsInput.before(PhysicsSet::SyncBackend))
+		.add_systems(OnEnter(GameState::InGame), resume_physics)
+		.add_systems(OnExit(GameState::InGame), pause_physics);
+	}
+}
+
+/// The simulation only steps while playing
+fn resume_physics(mut configs: Query<&mut RapierConfiguration, With<DefaultRapierContext>>) {
+	for mut config in configs.iter_mut() {
+		config.physics_pipeline_active = true;
+	}
+}
+
+fn pause_physics(mut configs: Query<&mut RapierConfiguration, With<DefaultRapierContext>>) {
+	for mut config in configs.iter_mut() {
+		config.physics_pipeline_active = false;
+	}
+}
+
+/// FixedUpdate systems that move bodies or character controllers before the physics step
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicsInput;
+
+/// Scene queries against every collider in the physics world
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w, 's> {
+	context: ReadDefaultRapierContext<'w, 's>,
+}
+
+impl SpatialQuery<'_, '_> {
+	/// Casts a ray and returns the point of the closest hit
+	pub fn raycast(&self, origin: Vec3, direction: Dir3, max_distance: f32, filter: QueryFilter) -> Option<Vec3> {
+		self.context.single()
+			.cast_ray(origin, direction.as_vec3(), max_distance, true, filter)
+			.map(|(_, distance)| origin + direction * distance)
+	}
+
+	/// Sweeps a shape along a direction and returns the hit entity with the distance travelled before touching it
+	pub fn shape_cast(&self, shape: &Collider, origin: Vec3, rotation: Quat, direction: Dir3, max_distance: f32, filter: QueryFilter) -> Option<(Entity, f32)> {
+		let options = ShapeCastOptions {
+			max_time_of_impact: max_distance,
+			stop_at_penetration: true,
+			..default()
+		};
+
+		self.context.single()
+			.cast_shape(origin, rotation, direction.as_vec3(), shape, options, filter)
+			.map(|(entity, hit)| (entity, hit.time_of_impact))
+	}
+
+	/// Returns the first collider overlapping a shape
+	pub fn overlap(&self, shape: &Collider, position: Vec3, rotation: Quat, filter: QueryFilter) -> Option<Entity> {
+		self.context.single().intersection_with_shape(position, rotation, shape, filter)
+	}
+}
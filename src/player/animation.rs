@@ -0,0 +1,236 @@
+use std::time::Duration;
+use bevy::{gltf::Gltf, log, prelude::*, utils::HashMap};
+use crate::state::GameState;
+use super::Player;
+use super::perspective::PlayerModel;
+
+const MODEL_PATH: &str = "models/Base_Character.glb";
+
+/// Plays the character clips from the player model, picked from the player's speed and grounded flag
+///
+/// The shipped `Base_Character.glb` has no skin and no clips, so nothing plays until it is replaced by a rigged model
+/// with clips named after the [`AnimationState`]s
+pub struct PlayerAnimationPlugin;
+
+impl Plugin for PlayerAnimationPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Startup, load_animations)
+		.add_systems(Update, (
+			build_animation_graph.run_if(resource_exists::<PlayerAnimations>),
+			attach_animation_players.run_if(animation_graph_ready),
+			update_animation_state.run_if(in_state(GameState::InGame)),
+		).chain());
+	}
+}
+
+/// What the character is doing, every state plays the clip with its name from the model
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AnimationState {
+	Idle,
+	Walk,
+	Run,
+	Jump,
+	Fall,
+}
+
+impl AnimationState {
+	const ALL: [AnimationState; 5] = [
+		AnimationState::Idle,
+		AnimationState::Walk,
+		AnimationState::Run,
+		AnimationState::Jump,
+		AnimationState::Fall,
+	];
+
+	fn clip_name(self) -> &'static str {
+		match self {
+			AnimationState::Idle => "Idle",
+			AnimationState::Walk => "Walk",
+			AnimationState::Run => "Run",
+			AnimationState::Jump => "Jump",
+			AnimationState::Fall => "Fall",
+		}
+	}
+
+	/// The state to play instead when the model has no clip for this one
+	fn fallback(self) -> Option<AnimationState> {
+		match self {
+			AnimationState::Idle => None,
+			AnimationState::Walk => Some(AnimationState::Idle),
+			AnimationState::Run => Some(AnimationState::Walk),
+			AnimationState::Jump => Some(AnimationState::Fall),
+			AnimationState::Fall => Some(AnimationState::Idle),
+		}
+	}
+
+	fn repeats(self) -> bool {
+		self != AnimationState::Jump
+	}
+}
+
+/// The clips of the player model and the graph built from them
+#[derive(Resource)]
+pub struct PlayerAnimations {
+	gltf: Handle<Gltf>,
+	graph: Option<Handle<AnimationGraph>>,
+	nodes: HashMap<AnimationState, AnimationNodeIndex>,
+	built: bool,
+	/// Seconds it takes to cross fade from one state to the next
+	pub fade_time: f32,
+	/// Horizontal speed below which the character idles, as a fraction of the walk speed
+	pub idle_threshold: f32,
+	/// Horizontal speed above which the character runs, as a fraction of the walk speed
+	pub run_threshold: f32,
+}
+
+impl PlayerAnimations {
+	/// The graph node of a state, falling back to similar states the model has clips for
+	fn node(&self, state: AnimationState) -> Option<(AnimationState, AnimationNodeIndex)> {
+		let mut state = Some(state);
+
+		while let Some(current) = state {
+			if let Some(node) = self.nodes.get(&current) {
+				return Some((current, *node));
+			}
+			state = current.fallback();
+		}
+
+		None
+	}
+}
+
+/// The state an animation player of the player model is in
+#[derive(Component, Default)]
+pub struct PlayerAnimator {
+	pub state: Option<AnimationState>,
+}
+
+fn load_animations(mut commands: Commands, asset_server: Res<AssetServer>) {
+	commands.insert_resource(PlayerAnimations {
+		gltf: asset_server.load(MODEL_PATH),
+		graph: None,
+		nodes: HashMap::default(),
+		built: false,
+		fade_time: 0.2,
+		idle_threshold: 0.1,
+		run_threshold: 1.2,
+	});
+}
+
+fn animation_graph_ready(animations: Option<Res<PlayerAnimations>>) -> bool {
+	animations.is_some_and(|animations| animations.graph.is_some())
+}
+
+/// Builds the animation graph once the model is loaded
+fn build_animation_graph(mut animations: ResMut<PlayerAnimations>, gltfs: Res<Assets<Gltf>>, mut graphs: ResMut<Assets<AnimationGraph>>) {
+	if animations.built {
+		return;
+	}
+
+	let Some(gltf) = gltfs.get(&animations.gltf) else {
+		return;
+	};
+	animations.built = true;
+
+	let mut graph = AnimationGraph::new();
+	let root = graph.root;
+	let mut nodes = HashMap::default();
+
+	for state in AnimationState::ALL {
+		if let Some(clip) = gltf.named_animations.get(state.clip_name()) {
+			nodes.insert(state, graph.add_clip(clip.clone(), 1.0, root));
+		}
+	}
+
+	if nodes.is_empty() {
+		log::warn!("Animation: {} has no Idle, Walk, Run, Jump or Fall clips, the player won't animate", MODEL_PATH);
+		return;
+	}
+
+	if !nodes.contains_key(&AnimationState::Idle) {
+		log::warn!("Animation: {} has no Idle clip, states without a clip keep the last one playing", MODEL_PATH);
+	}
+
+	animations.nodes = nodes;
+	animations.graph = Some(graphs.add(graph));
+}
+
+/// Gives the animation players spawned with the player model the graph and cross fading
+fn attach_animation_players(
+	mut commands: Commands,
+	animations: Res<PlayerAnimations>,
+	players: Query<Entity, (With<AnimationPlayer>, Without<PlayerAnimator>)>,
+	parents: Query<&Parent>,
+	models: Query<(), With<PlayerModel>>,
+) {
+	let Some(graph) = animations.graph.clone() else {
+		return;
+	};
+
+	for entity in players.iter() {
+		if !parents.iter_ancestors(entity).any(|ancestor| models.contains(ancestor)) {
+			continue;
+		}
+
+		commands.entity(entity).insert((
+			AnimationGraphHandle(graph.clone()),
+			AnimationTransitions::new(),
+			PlayerAnimator::default(),
+		));
+	}
+}
+
+/// Picks the state from the player's movement and cross fades to it
+fn update_animation_state(
+	animations: Option<Res<PlayerAnimations>>,
+	player_query: Query<&Player>,
+	mut animators: Query<(&mut PlayerAnimator, &mut AnimationPlayer, &mut AnimationTransitions)>,
+) {
+	let Some(animations) = animations else {
+		return;
+	};
+
+	let Ok(player) = player_query.get_single() else {
+		return;
+	};
+
+	let horizontal_speed = Vec2::new(player.velocity.x, player.velocity.z).length();
+	let walk_speed = player.speed.max(f32::EPSILON);
+	let run_speed = walk_speed * player.forward_sprint_speed.max(1.0);
+
+	let wanted = if !player.grounded {
+		if player.velocity.y > 0.0 { AnimationState::Jump } else { AnimationState::Fall }
+	} else if horizontal_speed < walk_speed * animations.idle_threshold {
+		AnimationState::Idle
+	} else if horizontal_speed < walk_speed * animations.run_threshold {
+		AnimationState::Walk
+	} else {
+		AnimationState::Run
+	};
+
+	let Some((state, node)) = animations.node(wanted) else {
+		return;
+	};
+
+	// Walking and running play faster or slower to match how fast the feet move
+	let playback_speed = match state {
+		AnimationState::Walk => (horizontal_speed / walk_speed).clamp(0.5, 2.0),
+		AnimationState::Run => (horizontal_speed / run_speed).clamp(0.5, 2.0),
+		_ => 1.0,
+	};
+
+	for (mut animator, mut animation_player, mut transitions) in animators.iter_mut() {
+		if animator.state != Some(state) {
+			animator.state = Some(state);
+
+			let animation = transitions.play(&mut animation_player, node, Duration::from_secs_f32(animations.fade_time));
+			if state.repeats() {
+				animation.repeat();
+			}
+		}
+
+		if let Some(animation) = animation_player.animation_mut(node) {
+			animation.set_speed(playback_speed);
+		}
+	}
+}
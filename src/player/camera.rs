@@ -1,16 +1,34 @@
 use bevy::{input::mouse::MouseMotion, prelude::*};
+use crate::input::ActionState;
+use crate::settings::Settings;
+use crate::window::Cursor;
+use super::third_person::damp;
 use super::zoom::Zoom;
 
 #[derive(Component)]
 pub struct CameraController {
 	pub is_first_person: bool,
     pub is_free_looking: bool,
+    /// Set after free look ends while the view eases back to the heading
+    pub is_returning: bool,
 	pub rotation: Vec2,
+    /// The heading the body faces, kept while free looking
     pub rotation_copy: Option<Vec2>,
+    /// How far in degrees free look can turn left or right of the heading
+    pub free_look_limit: f32,
+    /// How quickly the view returns to the heading after free look, higher is faster
+    pub free_look_return_speed: f32,
     pub rotation_lock: f32,
     pub sensitivity: f32,
+    /// Right stick turn speed in degrees per second
+    pub gamepad_sensitivity: f32,
+    pub invert_gamepad_y: bool,
     pub zoom: Zoom,
-    pub zoom_sensitivity: f32
+    pub zoom_sensitivity: f32,
+    /// 0 in first person and 1 in third person, eases between them when the view is toggled
+    pub perspective_blend: f32,
+    /// Seconds a full switch between first and third person takes
+    pub perspective_transition_time: f32,
 }
 
 impl Default for CameraController {
@@ -18,50 +36,110 @@ impl Default for CameraController {
 		CameraController {
 			is_first_person: true,
             is_free_looking: false,
+            is_returning: false,
 			rotation: Vec2::ZERO,
             rotation_copy: None,
+            free_look_limit: 100.0,
+            free_look_return_speed: 12.0,
 			rotation_lock: 88.0,
 			sensitivity: 0.5,
+			gamepad_sensitivity: 180.0,
+			invert_gamepad_y: false,
             zoom: Zoom::new(1.5, 3.0),
-			zoom_sensitivity: 1.0
+			zoom_sensitivity: 1.0,
+			perspective_blend: 0.0,
+			perspective_transition_time: 0.35,
 		}
 	}
 }
 
-/// Update the camera controller rotation
-pub fn update_camera_controller(mut mouse_motion: EventReader<MouseMotion>, mut camera_query: Query<(&mut CameraController, &mut Transform)>, input: Res<ButtonInput<KeyCode>>) {
-    if let Ok((mut camera_controller, mut transform)) = camera_query.get_single_mut() {
+impl CameraController {
+    /// The rotation the camera looks with, built from the yaw and pitch in degrees
+    pub fn target_rotation(&self) -> Quat {
+        let y_quat = Quat::from_axis_angle(Vec3::Y, self.rotation.y.to_radians());
+        let x_quat = Quat::from_axis_angle(Vec3::X, self.rotation.x.to_radians());
 
-        if input.just_released(KeyCode::AltLeft) {
-            camera_controller.rotation = camera_controller.rotation_copy.unwrap();
-            return;
+        -y_quat * -x_quat
+    }
+}
+
+/// Applies the camera settings to the camera controller and projection
+pub fn apply_camera_settings(settings: Res<Settings>, mut camera_query: Query<(&mut CameraController, &mut Projection)>) {
+    for (mut camera_controller, mut projection) in camera_query.iter_mut() {
+        camera_controller.sensitivity = settings.camera.sensitivity;
+        camera_controller.zoom_sensitivity = settings.camera.zoom_sensitivity;
+        camera_controller.rotation_lock = settings.camera.rotation_lock;
+        camera_controller.gamepad_sensitivity = settings.camera.gamepad_sensitivity;
+        camera_controller.invert_gamepad_y = settings.camera.invert_gamepad_y;
+
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = settings.camera.fov.to_radians();
         }
+    }
+}
+
+/// Update the camera controller rotation
+pub fn update_camera_controller(mut mouse_motion: EventReader<MouseMotion>, mut camera_query: Query<(&mut CameraController, &mut Transform)>, actions: Res<ActionState>, cursor: Res<Cursor>, time: Res<Time>) {
+    if let Ok((mut camera_controller, mut transform)) = camera_query.get_single_mut() {
 
         let mut rotation = Vec2::ZERO;
         for ev in mouse_motion.read() {
             rotation = ev.delta;
         }
 
-        if rotation.length_squared() == 0.0 {
-            return;
+        // The mouse only turns the camera while the game has the cursor
+        if cursor.is_locked() {
+            rotation *= camera_controller.sensitivity;
+        } else {
+            rotation = Vec2::ZERO;
         }
 
-        camera_controller.rotation.y -= rotation.x * camera_controller.sensitivity;
-        camera_controller.rotation.x -= rotation.y * camera_controller.sensitivity;
-        camera_controller.rotation.x = f32::clamp(camera_controller.rotation.x, -camera_controller.rotation_lock, camera_controller.rotation_lock);
+        // Right stick look, pushing the stick up looks up
+        let look_y = if camera_controller.invert_gamepad_y { actions.look.y } else { -actions.look.y };
+        rotation += Vec2::new(actions.look.x, look_y) * camera_controller.gamepad_sensitivity * time.delta_secs();
 
-        let y_quat = Quat::from_axis_angle(Vec3::Y, camera_controller.rotation.y.to_radians());
-        let x_quat = Quat::from_axis_angle(Vec3::X, camera_controller.rotation.x.to_radians());
+        if rotation.length_squared() > 0.0 {
+            camera_controller.rotation.y -= rotation.x;
+            camera_controller.rotation.x -= rotation.y;
+            camera_controller.rotation.x = f32::clamp(camera_controller.rotation.x, -camera_controller.rotation_lock, camera_controller.rotation_lock);
+
+            let rotation_lock = camera_controller.rotation_lock;
+            let free_look_limit = camera_controller.free_look_limit;
+
+            if camera_controller.is_free_looking {
+                // The head can only turn so far from the body
+                if let Some(heading) = camera_controller.rotation_copy {
+                    camera_controller.rotation.y = camera_controller.rotation.y.clamp(heading.y - free_look_limit, heading.y + free_look_limit);
+                }
+            } else if camera_controller.is_returning {
+                // Looking around while the view returns turns the heading with it
+                if let Some(heading) = camera_controller.rotation_copy.as_mut() {
+                    heading.y -= rotation.x;
+                    heading.x = (heading.x - rotation.y).clamp(-rotation_lock, rotation_lock);
+                }
+            } else {
+                camera_controller.rotation_copy = Some(camera_controller.rotation);
+            }
+        }
 
-        transform.rotation = -y_quat * -x_quat;
+        if camera_controller.is_returning {
+            match camera_controller.rotation_copy {
+                Some(heading) => {
+                    let amount = damp(camera_controller.free_look_return_speed, time.delta_secs());
+                    camera_controller.rotation = camera_controller.rotation.lerp(heading, amount);
 
-        if !camera_controller.is_free_looking {
-            camera_controller.rotation_copy = Some(camera_controller.rotation);
+                    if camera_controller.rotation.distance_squared(heading) < 0.01 {
+                        camera_controller.rotation = heading;
+                        camera_controller.is_returning = false;
+                    }
+                }
+                None => camera_controller.is_returning = false,
+            }
         }
 
-        if !camera_controller.is_first_person {
-            let rot_matrix = Mat3::from_quat(transform.rotation);
-            transform.translation = rot_matrix.mul_vec3(Vec3::new(0.0, 5.5, 5.0 + camera_controller.zoom.radius));
+        // The third person rig places and turns the camera itself
+        if camera_controller.is_first_person {
+            transform.rotation = camera_controller.target_rotation();
         }
     }
 }
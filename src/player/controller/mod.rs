@@ -1,14 +1,20 @@
 use bevy::{log, prelude::*};
-use bevy_rapier3d::prelude::*;
+use crate::input::{Action, ActionState};
+use crate::state::GameState;
+use crate::physics::{KinematicCharacterController, KinematicCharacterControllerOutput};
 
 use super::Player;
 use super::camera::CameraController;
+use super::health::{Dead, PlayerLanded};
+use super::spawn::AwaitingSpawn;
+use super::stamina::Stamina;
+use super::stance::Stance;
 
 pub struct PlayerControllerPlugin;
 
 impl Plugin for PlayerControllerPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_systems(Update, (input_movement, update_camera_perspective, free_look));
+		app.add_systems(Update, (input_movement, update_camera_perspective, free_look).run_if(in_state(GameState::InGame)));
 	}
 }
 
@@ -17,7 +23,15 @@ pub struct PlayerControllerState {
 	pub movement: Vec2,
 	pub position: Vec3,
 	pub sprint_speed: f32,
-	pub direction: Vec2,
+	/// Set while the player is sprinting and using stamina
+	pub sprinting: bool,
+	/// Set when jump is pressed and cleared once the fixed update has buffered it
+	pub jump_requested: bool,
+	pub jump_held: bool,
+	/// The stance asked for with the crouch and prone actions
+	pub wanted_stance: Stance,
+	/// The stance the player is in, lags behind the wanted one while a ceiling is in the way
+	pub stance: Stance,
 }
 
 impl Default for PlayerControllerState {
@@ -26,88 +40,60 @@ impl Default for PlayerControllerState {
 			movement: Vec2::ZERO,
 			position: Vec3::ZERO,
 			sprint_speed: 0.0,
-			direction: Vec2::ZERO,
+			sprinting: false,
+			jump_requested: false,
+			jump_held: false,
+			wanted_stance: Stance::Standing,
+			stance: Stance::Standing,
 		}
 	}
 }
 
-#[derive(Component)]
-pub struct PlayerController {
-	pub move_left: KeyCode,
-	pub move_right: KeyCode,
-	pub move_forward: KeyCode,
-	pub move_back: KeyCode,
-	pub sprint: KeyCode,
-	pub jump: KeyCode,
-	pub main_menu: KeyCode,
-	pub camera_perspective: KeyCode,
-	pub free_look: KeyCode,
-	pub inventory: KeyCode,
-}
-
-impl Default for PlayerController {
-	fn default() -> Self {
-		PlayerController {
-			move_left: KeyCode::KeyA,
-			move_right: KeyCode::KeyD,
-			move_forward: KeyCode::KeyW,
-			move_back: KeyCode::KeyS,
-			sprint: KeyCode::ShiftLeft,
-			jump: KeyCode::Space,
-			main_menu: KeyCode::Escape,
-			camera_perspective: KeyCode::ArrowUp,
-			free_look: KeyCode::AltLeft,
-			inventory: KeyCode::Tab,
-		}
-	}
-}
+/// Marks the entity driven by the local player's input actions
+#[derive(Component, Default)]
+pub struct PlayerController;
 
 /// Player movement system
-pub fn input_movement(keys: Res<ButtonInput<KeyCode>>, mut player: Query<(&PlayerController, &mut Player, &Transform), With<Player>>, mut input: ResMut<PlayerControllerState>) {
-	for (controller, player, transform) in player.iter_mut() {
+pub fn input_movement(actions: Res<ActionState>, mut player: Query<(&Player, &Stamina, &Transform, Has<Dead>), With<PlayerController>>, mut input: ResMut<PlayerControllerState>) {
+	for (player, stamina, transform, dead) in player.iter_mut() {
 		// Set the players position
 		input.position = transform.translation;
-		
-		input.movement = Vec2::ZERO;
-		input.sprint_speed = 1.0;
-
-		// Move forward
-		if keys.pressed(controller.move_forward) {
-			if keys.pressed(controller.sprint) && player.sprint_enabled {
-				input.sprint_speed = player.forward_sprint_speed;
-			}
-
-			input.movement.x += 1.;
-		}
-
-		// Move back
-		if keys.pressed(controller.move_back) {
-			if keys.pressed(controller.sprint) && player.sprint_enabled {
-				input.sprint_speed = player.backward_sprint_speed;
-			}
 
-			input.movement.x -= 1.;
+		// The dead don't move
+		if dead {
+			input.movement = Vec2::ZERO;
+			input.sprinting = false;
+			input.sprint_speed = 1.0;
+			input.jump_requested = false;
+			input.jump_held = false;
+			continue;
 		}
+		
+		// Analog movement, keys give full strength and sticks keep their deflection
+		input.movement = actions.movement;
 
-		// Move left
-		if keys.pressed(controller.move_left) {
-			input.movement.y -= 1.;
-		}
+		// Sprint only while mostly moving forward and there's stamina left
+		let moving_forward = input.movement.x > 0.0 && input.movement.x >= input.movement.y.abs();
+		input.sprinting = actions.pressed(Action::Sprint) && player.sprint_enabled && moving_forward && stamina.can_sprint() && player.stance == Stance::Standing;
+		input.sprint_speed = if input.sprinting { player.forward_sprint_speed } else { 1.0 };
 
-		// Move right
-		if keys.pressed(controller.move_right) {
-			input.movement.y += 1.;
+		// Crouch and prone toggle, pressing the same one again stands back up
+		if actions.just_pressed(Action::Crouch) {
+			input.wanted_stance = if input.wanted_stance == Stance::Crouching { Stance::Standing } else { Stance::Crouching };
 		}
-
-		// Jump
-		if keys.pressed(controller.jump) {
-			//input.movement.z += 1.;
+		if actions.just_pressed(Action::Prone) {
+			input.wanted_stance = if input.wanted_stance == Stance::Prone { Stance::Standing } else { Stance::Prone };
 		}
 
-		// Main Menu
-		if keys.pressed(controller.main_menu) {
-			std::process::exit(0);
+		// Jump, or stand up when low
+		if actions.just_pressed(Action::Jump) {
+			if input.wanted_stance == Stance::Standing {
+				input.jump_requested = true;
+			} else {
+				input.wanted_stance = Stance::Standing;
+			}
 		}
+		input.jump_held = actions.pressed(Action::Jump);
 	}
 }
 
@@ -116,88 +102,145 @@ pub fn update_movement(
     time : Res<Time<Fixed>>,
     mut input : ResMut<PlayerControllerState>,
     camera_query : Query<&CameraController>,
-    mut player_query : Query<(&mut Player, &mut Transform, &mut KinematicCharacterController, Option<&KinematicCharacterControllerOutput>)>,
+    mut player_query : Query<(Entity, &mut Player, &mut Transform, &mut KinematicCharacterController, Option<&KinematicCharacterControllerOutput>), Without<AwaitingSpawn>>,
+    mut landed : EventWriter<PlayerLanded>,
 ){
     let Ok(camera) = camera_query.get_single() else {
 		log::error!("Camera not found");
 		return;
 	};
 
-    for(mut player, mut transform, mut controller, controller_output) in player_query.iter_mut() {
-        if let Some(output) = controller_output{
-            if output.grounded {
-                player.velocity = Vec3::ZERO;
+    for(entity, mut player, mut transform, mut controller, controller_output) in player_query.iter_mut() {
+        let delta = time.timestep().as_secs_f32();
+
+        // Keep the controller in sync with the parameters on the player
+        player.configure_controller(&mut controller);
+
+        if let Some(output) = controller_output {
+            player.clamp_to_movement(output.desired_translation, output.effective_translation, delta);
+
+            // Ignore the ground while still rising from a jump
+            let was_grounded = player.grounded;
+            player.grounded = output.grounded && player.velocity.y <= 0.0;
+
+            if player.grounded && !was_grounded {
+                landed.send(PlayerLanded { entity, speed: -player.velocity.y });
             }
-        }
 
-		// Get the camera current rotation in Radians
-        let camera_rotation_converted = -camera.rotation.y.to_radians() - 90.0_f32.to_radians();
+            if player.grounded {
+                player.velocity.y = 0.0;
+            } else if output.desired_translation.y > 0.0 && output.effective_translation.y < output.desired_translation.y * 0.5 {
+                // Hit a ceiling, start falling
+                player.velocity.y = 0.0;
+            }
+        }
 
-		// The forward direction of the camera
-		// If free looking use the last looked direction else calculate the new direction
-		let forward = if camera.is_free_looking {
-			input.direction
+		// While free looking or returning from it the body keeps walking along the stored heading
+		let heading = if camera.is_free_looking || camera.is_returning {
+			camera.rotation_copy.map_or(camera.rotation.y, |heading| heading.y)
 		} else {
-			// Calculate the forward direction of the camera
-			let forward = Vec2::new(f32::cos(camera_rotation_converted), f32::sin(camera_rotation_converted));
-			input.direction = forward;
-			forward
+			camera.rotation.y
 		};
 
+		// Get the heading in Radians
+        let heading_converted = -heading.to_radians() - 90.0_f32.to_radians();
+
+		// The forward direction of the body
+		let forward = Vec2::new(f32::cos(heading_converted), f32::sin(heading_converted));
+
 		// The right direction of the camera perpendicular to the forward direction
         let right = Vec2::new(-forward.y, forward.x);
 
-		if let Some(movement_direction) = (forward * input.movement.x + right * input.movement.y).try_normalize() {
-			player.velocity.x = movement_direction.x * player.speed * input.sprint_speed;
-			player.velocity.z = movement_direction.y * player.speed * input.sprint_speed;
+		// The movement keeps its analog strength so a half tilted stick walks at half speed
+		let movement_direction = (forward * input.movement.x + right * input.movement.y).clamp_length_max(1.0);
+		let wanted_velocity = movement_direction * player.speed * player.speed_multiplier() * input.sprint_speed;
+		player.accelerate(wanted_velocity, delta);
+
+		update_jump(&mut player, &mut input, delta);
+
+		// Don't snap back onto the ground while rising
+		if player.velocity.y > 0.0 {
+			controller.snap_to_ground = None;
 		}
 
 		// Apply gravity
-        player.velocity.y -= player.gravity * time.timestep().as_secs_f32();
+        player.velocity.y = (player.velocity.y - player.gravity * delta).max(-player.max_fall_speed);
 
-        // Apply translation
-        controller.translation = Some(player.velocity * time.timestep().as_secs_f32());
+        // Apply translation, with the move to the center of a new stance
+        controller.translation = Some(player.velocity * delta + Vec3::Y * player.stance_shift);
+        player.stance_shift = 0.0;
 
-		// Rotate the player to face the direction of movement
-		//transform.look_to(Vec3::new(forward.x, 0.0, forward.y), Vec3::Y);
+		// Face the camera heading in first person and the direction of movement in third person
+		let facing = if camera.is_first_person { forward } else { movement_direction };
+		if let Ok(facing) = Dir3::new(Vec3::new(facing.x, 0.0, facing.y)) {
+			let target = Transform::IDENTITY.looking_to(facing, Vec3::Y).rotation;
+			transform.rotation = transform.rotation.rotate_towards(target, player.turn_rate.to_radians() * delta);
+		}
     }
 }
 
-/// Update camera perspective system which allows a player to go between first and third person
-fn update_camera_perspective(mut keys: ResMut<ButtonInput<KeyCode>>, player: Query<&PlayerController, With<Player>>, mut camera_query: Query<(&mut CameraController, &mut Transform)>) {
-	let Some(controller) = player.iter().next() else {
-		log::error!("Camera Perspective: PlayerController not found");
-		return;
-	};
+/// Handles jumping with coyote time, input buffering and a shorter jump when the key is released early
+fn update_jump(player: &mut Player, input: &mut PlayerControllerState, delta: f32) {
+	if player.grounded {
+		player.coyote_timer = player.coyote_time;
+		player.is_jumping = false;
+	} else {
+		player.coyote_timer = (player.coyote_timer - delta).max(0.0);
+	}
 
-	if keys.just_pressed(controller.camera_perspective) {
-		for (mut cam, mut transform) in camera_query.iter_mut() {
-			log::info!("Camera Perspective: {:?}", transform.translation);
-			if cam.is_first_person {
-				cam.is_first_person = false;
-				transform.translation = Vec3::new(0.0, 5.5, 5.0);
-				log::info!("Camera Perspective: {:?}", transform.translation);
-			} else {
-				cam.is_first_person = true;
-				transform.translation = Vec3::new(0.0, 0.0, 0.0);
-			}
+	if input.jump_requested {
+		input.jump_requested = false;
+		player.jump_buffer_timer = player.jump_buffer_time;
+	} else {
+		player.jump_buffer_timer = (player.jump_buffer_timer - delta).max(0.0);
+	}
+
+	if player.jump_buffer_timer > 0.0 && player.coyote_timer > 0.0 {
+		player.velocity.y = (2.0 * player.gravity * player.jump_height).sqrt();
+		player.jump_buffer_timer = 0.0;
+		player.coyote_timer = 0.0;
+		player.is_jumping = true;
+		player.grounded = false;
+	}
+
+	if player.is_jumping {
+		if player.velocity.y <= 0.0 {
+			player.is_jumping = false;
+		} else if !input.jump_held {
+			player.velocity.y *= player.jump_release_multiplier;
+			player.is_jumping = false;
+		}
+	}
+}
+
+/// Update camera perspective system which allows a player to go between first and third person
+fn update_camera_perspective(mut actions: ResMut<ActionState>, mut camera_query: Query<&mut CameraController>) {
+	if actions.just_pressed(Action::ToggleView) {
+		for mut cam in camera_query.iter_mut() {
+			// The camera eases to the new view over the transition time
+			cam.is_first_person = !cam.is_first_person;
+			log::info!("Camera Perspective: {}", if cam.is_first_person { "first person" } else { "third person" });
 		};
-		keys.clear_just_pressed(controller.camera_perspective);
+		actions.consume(Action::ToggleView);
 	}
 }
 
 /// Allows the user to free look as if they're turning their head
-fn free_look(keys: Res<ButtonInput<KeyCode>>, player: Query<&PlayerController, With<Player>>, mut camera_query: Query<&mut CameraController>) {
-	let Some(controller) = player.iter().next() else {
-		log::error!("Free look: PlayerController not found");
-		return;
-	};
-
+fn free_look(actions: Res<ActionState>, mut camera_query: Query<&mut CameraController>) {
 	if let Ok(mut camera_controller) = camera_query.get_single_mut() {
-		if keys.pressed(controller.free_look) {
+		let held = actions.pressed(Action::FreeLook);
+
+		if held && !camera_controller.is_free_looking {
 			camera_controller.is_free_looking = true;
-		} else {
+			camera_controller.is_returning = false;
+
+			// Keep the heading the body is facing, the camera may not have moved yet
+			if camera_controller.rotation_copy.is_none() {
+				camera_controller.rotation_copy = Some(camera_controller.rotation);
+			}
+		} else if !held && camera_controller.is_free_looking {
 			camera_controller.is_free_looking = false;
+			camera_controller.is_returning = camera_controller.rotation_copy.is_some();
 		}
 	}
 }
\ No newline at end of file
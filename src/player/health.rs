@@ -0,0 +1,196 @@
+use bevy::{log, prelude::*};
+use crate::level::terrain::Terrain;
+use crate::state::GameState;
+use super::Player;
+use super::controller::PlayerControllerState;
+use super::spawn::AwaitingSpawn;
+use super::stance::Stance;
+
+/// Health, fall damage, death and respawning
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_event::<PlayerLanded>()
+		.add_event::<PlayerDamaged>()
+		.add_event::<PlayerDied>()
+		.add_event::<PlayerRespawned>()
+		.add_systems(Update, (
+			apply_fall_damage,
+			kill_out_of_world,
+			update_death,
+			respawn,
+		).chain().run_if(in_state(GameState::InGame)));
+	}
+}
+
+/// Hit points, the player dies when they reach zero
+#[derive(Component)]
+pub struct Health {
+	pub current: f32,
+	pub max: f32,
+}
+
+impl Default for Health {
+	fn default() -> Self {
+		Health { current: 100.0, max: 100.0 }
+	}
+}
+
+impl Health {
+	pub fn is_dead(&self) -> bool {
+		self.current <= 0.0
+	}
+
+	/// The health left from 0 to 1
+	pub fn fraction(&self) -> f32 {
+		if self.max > 0.0 { (self.current / self.max).clamp(0.0, 1.0) } else { 0.0 }
+	}
+}
+
+/// How hard landings hurt
+#[derive(Component)]
+pub struct FallDamage {
+	/// Landing speed that doesn't hurt yet
+	pub safe_speed: f32,
+	/// Damage per unit of landing speed above the safe speed
+	pub damage_per_speed: f32,
+	/// Falling this far below the lowest ground kills the player, that's the bottom of the terrain or y 0 without one
+	pub kill_depth: f32,
+}
+
+impl Default for FallDamage {
+	fn default() -> Self {
+		FallDamage {
+			safe_speed: 12.0,
+			damage_per_speed: 6.0,
+			kill_depth: 100.0,
+		}
+	}
+}
+
+/// Added while the player is dead, it respawns when the timer finishes
+#[derive(Component)]
+pub struct Dead {
+	pub respawn_timer: Timer,
+}
+
+/// Why the player lost health
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageCause {
+	Fall,
+	OutOfWorld,
+}
+
+/// Sent by the movement when the player touches the ground after being in the air
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerLanded {
+	pub entity: Entity,
+	/// Downward speed at the moment of landing
+	pub speed: f32,
+}
+
+/// Sent whenever the player loses health
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDamaged {
+	pub entity: Entity,
+	pub amount: f32,
+	pub cause: DamageCause,
+}
+
+/// Sent when the player's health reaches zero
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDied {
+	pub entity: Entity,
+	pub cause: DamageCause,
+}
+
+/// Sent after a dead player is put back at a spawn point
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerRespawned {
+	pub entity: Entity,
+	pub position: Vec3,
+}
+
+/// Seconds between dying and respawning
+const RESPAWN_DELAY: f32 = 3.0;
+
+/// Takes health off for every landing faster than the safe speed
+fn apply_fall_damage(
+	mut landings: EventReader<PlayerLanded>,
+	mut player_query: Query<(&mut Health, &FallDamage), Without<Dead>>,
+	mut damaged: EventWriter<PlayerDamaged>,
+) {
+	for landing in landings.read() {
+		let Ok((mut health, fall_damage)) = player_query.get_mut(landing.entity) else {
+			continue;
+		};
+
+		let amount = (landing.speed - fall_damage.safe_speed) * fall_damage.damage_per_speed;
+		if amount <= 0.0 {
+			continue;
+		}
+
+		health.current = (health.current - amount).max(0.0);
+		damaged.send(PlayerDamaged { entity: landing.entity, amount, cause: DamageCause::Fall });
+	}
+}
+
+/// Kills the player after falling off the level
+fn kill_out_of_world(
+	terrain: Option<Res<Terrain>>,
+	mut player_query: Query<(Entity, &Transform, &mut Health, &FallDamage), Without<Dead>>,
+	mut damaged: EventWriter<PlayerDamaged>,
+) {
+	// A zero heightmap sample is as low as the terrain goes
+	let lowest_ground = terrain.map_or(0.0, |terrain| terrain.origin.y.min(0.0));
+
+	for (entity, transform, mut health, fall_damage) in player_query.iter_mut() {
+		if transform.translation.y < lowest_ground - fall_damage.kill_depth && !health.is_dead() {
+			let amount = health.current;
+			health.current = 0.0;
+			damaged.send(PlayerDamaged { entity, amount, cause: DamageCause::OutOfWorld });
+		}
+	}
+}
+
+/// Marks players without health as dead
+fn update_death(
+	mut commands: Commands,
+	mut damaged: EventReader<PlayerDamaged>,
+	player_query: Query<&Health, Without<Dead>>,
+	mut died: EventWriter<PlayerDied>,
+) {
+	for damage in damaged.read() {
+		let Ok(health) = player_query.get(damage.entity) else {
+			continue;
+		};
+
+		if health.is_dead() {
+			log::info!("Player died: {:?}", damage.cause);
+			commands.entity(damage.entity).insert(Dead { respawn_timer: Timer::from_seconds(RESPAWN_DELAY, TimerMode::Once) });
+			died.send(PlayerDied { entity: damage.entity, cause: damage.cause });
+		}
+	}
+}
+
+/// Sends dead players back to the level's spawn point with full health once the delay is over
+fn respawn(
+	mut commands: Commands,
+	time: Res<Time>,
+	mut input: ResMut<PlayerControllerState>,
+	mut player_query: Query<(Entity, &mut Dead, &mut Health, &mut Player)>,
+) {
+	for (entity, mut dead, mut health, mut player) in player_query.iter_mut() {
+		if !dead.respawn_timer.tick(time.delta()).finished() {
+			continue;
+		}
+
+		input.wanted_stance = Stance::Standing;
+		player.velocity = Vec3::ZERO;
+		health.current = health.max;
+
+		// `PlayerRespawned` is sent once the player is placed at the spawn point
+		commands.entity(entity).remove::<Dead>().insert(AwaitingSpawn::respawn());
+	}
+}
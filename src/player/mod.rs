@@ -1,20 +1,44 @@
 use bevy::{log, prelude::*};
-use avian3d::prelude::*;
+use crate::settings::Settings;
+use crate::state::GameState;
+use crate::physics::{CharacterAutostep, CharacterLength, Collider, KinematicCharacterController, PhysicsInput, RigidBody};
 
 pub mod controller;
+mod animation;
 mod camera;
+mod health;
+mod perspective;
+mod spawn;
+mod stamina;
+mod stance;
+mod third_person;
 mod zoom;
 
+pub use health::{DamageCause, Health, PlayerDamaged, PlayerDied, PlayerRespawned};
+pub use stamina::Stamina;
+pub use stance::Stance;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
 	fn build(&self, app: &mut App) {
 		app
 		.init_resource::<controller::PlayerControllerState>()
-		.add_plugins(controller::PlayerControllerPlugin)
+		.add_plugins((controller::PlayerControllerPlugin, animation::PlayerAnimationPlugin, health::HealthPlugin))
+		.add_event::<spawn::PlayerSpawned>()
 		.add_systems(Startup, spawn_player)
-		.add_systems(Update, (camera::update_camera_controller, zoom::zoom))
-		.add_systems(FixedUpdate, controller::update_movement);
+		.add_systems(Update, spawn::place_at_spawn_point.run_if(in_state(GameState::InGame)))
+		.add_systems(Update, (
+			camera::update_camera_controller,
+			zoom::zoom,
+			perspective::update_perspective_blend,
+			third_person::snap_on_spawn,
+			third_person::update_third_person_camera,
+			perspective::update_model_visibility,
+		).chain().run_if(in_state(GameState::InGame)))
+		.add_systems(Update, stamina::update_stamina.after(controller::input_movement).run_if(in_state(GameState::InGame)))
+		.add_systems(Update, camera::apply_camera_settings.run_if(resource_changed::<Settings>))
+		.add_systems(FixedUpdate, (stance::update_stance, controller::update_movement).chain().in_set(PhysicsInput).run_if(in_state(GameState::InGame)));
 	}
 }
 
@@ -24,8 +48,58 @@ pub struct Player {
     pub gravity : f32,
 	pub speed: f32,
 	pub forward_sprint_speed: f32,
-	pub backward_sprint_speed: f32,
 	pub sprint_enabled: bool,
+	/// How quickly the player speeds up towards the wanted speed on the ground, in units per second squared
+	pub acceleration: f32,
+	/// How quickly the player slows down when turning or letting go of sprint on the ground
+	pub deceleration: f32,
+	/// How quickly the player comes to a stop on the ground with no input
+	pub friction: f32,
+	/// Multiplies acceleration, deceleration and friction while in the air
+	pub air_control: f32,
+	/// Highest horizontal speed, sprinting included
+	pub max_speed: f32,
+	/// Highest falling speed
+	pub max_fall_speed: f32,
+	/// Set by the character controller after every physics step
+	pub grounded: bool,
+	pub capsule_radius: f32,
+	/// Half height of the capsule's cylinder while standing
+	pub capsule_half_height: f32,
+	pub stance: Stance,
+	/// How far a stance change moves the center this step, applied through the character controller with the movement
+	pub stance_shift: f32,
+	/// Half height of the capsule's cylinder while crouching
+	pub crouch_half_height: f32,
+	/// Half length of the capsule's cylinder while prone, the capsule then lies along the body
+	pub prone_half_length: f32,
+	/// Multiplies the speed while crouching
+	pub crouch_speed_multiplier: f32,
+	/// Multiplies the speed while prone
+	pub prone_speed_multiplier: f32,
+	/// Distance from the top of the capsule down to the first person camera
+	pub eye_offset: f32,
+	/// Steepest slope in degrees the player can walk up
+	pub max_slope_angle: f32,
+	/// Slopes steeper than this in degrees make the player slide down
+	pub min_slide_angle: f32,
+	/// Highest ledge the player automatically steps onto
+	pub step_height: f32,
+	/// Distance below the player within which it sticks to the ground when walking down slopes or stairs
+	pub snap_to_ground: f32,
+	/// Height of a jump when the key is held
+	pub jump_height: f32,
+	/// Seconds after walking off a ledge during which the player can still jump
+	pub coyote_time: f32,
+	/// Seconds a jump pressed in the air is remembered before landing
+	pub jump_buffer_time: f32,
+	/// Multiplies the upward velocity when the jump key is released before the top of the jump
+	pub jump_release_multiplier: f32,
+	/// How fast the body turns to face where it's going in degrees per second
+	pub turn_rate: f32,
+	pub coyote_timer: f32,
+	pub jump_buffer_timer: f32,
+	pub is_jumping: bool,
 }
 
 impl Default for Player {
@@ -35,41 +109,177 @@ impl Default for Player {
 			gravity: 9.8,
 			speed: 20.0,
 			forward_sprint_speed: 2.0,
-			backward_sprint_speed: 1.1,
 			sprint_enabled: true,
+			acceleration: 120.0,
+			deceleration: 160.0,
+			friction: 100.0,
+			air_control: 0.3,
+			max_speed: 40.0,
+			max_fall_speed: 50.0,
+			grounded: false,
+			capsule_radius: 0.4,
+			capsule_half_height: 0.5,
+			stance: Stance::Standing,
+			stance_shift: 0.0,
+			crouch_half_height: 0.15,
+			prone_half_length: 0.5,
+			crouch_speed_multiplier: 0.5,
+			prone_speed_multiplier: 0.25,
+			eye_offset: 0.15,
+			max_slope_angle: 45.0,
+			min_slide_angle: 30.0,
+			step_height: 0.35,
+			snap_to_ground: 0.3,
+			jump_height: 2.5,
+			coyote_time: 0.12,
+			jump_buffer_time: 0.15,
+			jump_release_multiplier: 0.5,
+			turn_rate: 720.0,
+			coyote_timer: 0.0,
+			jump_buffer_timer: 0.0,
+			is_jumping: false,
 		}
 	}
 }
 
+impl Player {
+	/// The capsule collider matching the player's dimensions in the current stance
+	pub fn collider(&self) -> Collider {
+		self.stance_collider(self.stance, self.capsule_radius)
+	}
+
+	/// The capsule of a stance with a radius, upright when standing or crouching and lying along the body when prone
+	pub fn stance_collider(&self, stance: Stance, radius: f32) -> Collider {
+		match stance {
+			Stance::Prone => Collider::capsule_z(self.prone_half_length, radius),
+			_ => Collider::capsule_y(self.stance_half_height(stance), radius),
+		}
+	}
+
+	/// Total height of the capsule in the current stance, from the feet to the top of the head
+	pub fn height(&self) -> f32 {
+		self.stance_height(self.stance)
+	}
+
+	/// Half height of the capsule's cylinder in a stance, the prone capsule lies down so it's only as tall as it is wide
+	pub fn stance_half_height(&self, stance: Stance) -> f32 {
+		match stance {
+			Stance::Standing => self.capsule_half_height,
+			Stance::Crouching => self.crouch_half_height,
+			Stance::Prone => 0.0,
+		}
+	}
+
+	/// Total height of the capsule in a stance
+	pub fn stance_height(&self, stance: Stance) -> f32 {
+		(self.stance_half_height(stance) + self.capsule_radius) * 2.0
+	}
+
+	/// Height of the first person camera above the feet
+	pub fn eye_height(&self) -> f32 {
+		(self.height() - self.eye_offset).max(self.capsule_radius)
+	}
+
+	/// Multiplies the speed in the current stance
+	pub fn speed_multiplier(&self) -> f32 {
+		match self.stance {
+			Stance::Standing => 1.0,
+			Stance::Crouching => self.crouch_speed_multiplier,
+			Stance::Prone => self.prone_speed_multiplier,
+		}
+	}
+
+	/// Moves the horizontal velocity towards the wanted one, the rates are reduced in the air
+	pub fn accelerate(&mut self, wanted: Vec2, delta: f32) {
+		let current = Vec2::new(self.velocity.x, self.velocity.z);
+		let wanted = wanted.clamp_length_max(self.max_speed);
+		let control = if self.grounded { 1.0 } else { self.air_control };
+
+		let rate = if wanted == Vec2::ZERO {
+			self.friction
+		} else if wanted.dot(current) < 0.0 || wanted.length_squared() < current.length_squared() {
+			self.deceleration
+		} else {
+			self.acceleration
+		};
+
+		let velocity = current.move_towards(wanted, rate * control * delta).clamp_length_max(self.max_speed);
+		self.velocity.x = velocity.x;
+		self.velocity.z = velocity.y;
+	}
+
+	/// Cuts the horizontal velocity down to what the character controller managed to move last step,
+	/// so speed doesn't build up against a wall and launch the player once the wall ends
+	pub fn clamp_to_movement(&mut self, desired: Vec3, effective: Vec3, delta: f32) {
+		let desired = desired.xz();
+		let effective = effective.xz();
+
+		if delta > 0.0 && effective.length_squared() < desired.length_squared() {
+			let velocity = effective / delta;
+			self.velocity.x = velocity.x;
+			self.velocity.z = velocity.y;
+		}
+	}
+
+	/// Applies the movement parameters to the character controller
+	pub fn configure_controller(&self, controller: &mut KinematicCharacterController) {
+		controller.up = Vec3::Y;
+		controller.slide = true;
+		controller.offset = CharacterLength::Absolute(0.01);
+		controller.max_slope_climb_angle = self.max_slope_angle.to_radians();
+		controller.min_slope_slide_angle = self.min_slide_angle.to_radians();
+		controller.autostep = Some(CharacterAutostep {
+			max_height: CharacterLength::Absolute(self.step_height),
+			min_width: CharacterLength::Absolute(self.capsule_radius * 0.5),
+			include_dynamic_bodies: false,
+		});
+		controller.snap_to_ground = Some(CharacterLength::Absolute(self.snap_to_ground));
+	}
+}
+
 /// Player spawn system
-fn spawn_player(mut commands: Commands, meshes: Res<Assets<Mesh>>, asset_server: Res<AssetServer>) {
+fn spawn_player(mut commands: Commands, meshes: Res<Assets<Mesh>>, asset_server: Res<AssetServer>, settings: Res<Settings>) {
 	let handle: Handle<Scene> = asset_server.load("models/Base_Character.glb#Scene0");
 
 	let camera = commands.spawn((
 		Camera3d::default(),
 		Transform::IDENTITY,
 		Projection::Perspective(PerspectiveProjection {
-			fov: 103.0_f32.to_radians(),
+			fov: settings.camera.fov.to_radians(),
 			..default()
 		}),
         camera::CameraController::default(),
+		third_person::ThirdPersonRig::default(),
 	)).id();
 
+	let player_data = Player::default();
+	let mut character_controller = KinematicCharacterController::default();
+	player_data.configure_controller(&mut character_controller);
+
+	// The model's feet are at its origin, the player root is the center of the capsule
 	let player_entity = commands.spawn((
 		SceneRoot(handle.clone()),
-		Transform::IDENTITY,
-		ColliderConstructorHierarchy::new(ColliderConstructor::ConvexDecompositionFromMesh),
-		RigidBody::Kinematic,
+		Transform::from_xyz(0.0, -player_data.height() / 2.0, 0.0),
+		perspective::PlayerModel,
 	)).id();
 
 	let player = commands.spawn((
+		// Moved to the level's spawn point once it's known
 		Transform::from_xyz(0.0, 0.0, 0.0),
-		Player::default(), 
-		controller::PlayerController::default(),
+		player_data.collider(),
+		RigidBody::KinematicPositionBased,
+		character_controller,
+		player_data,
+		Stamina::default(),
+		Health::default(),
+		spawn::AwaitingSpawn::default(),
+		health::FallDamage::default(),
+		controller::PlayerController,
 		Visibility::Visible,
 	)).id();
 
 	commands.entity(camera);
-    commands.entity(player).add_child(player_entity).add_child(camera);
+	// The camera isn't a child of the player so turning the body doesn't turn the view
+    commands.entity(player).add_child(player_entity);
 }
 //0.0015340410077627612 4096, 2074
\ No newline at end of file
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use super::camera::CameraController;
+
+/// Below this blend the camera is inside the body so the model is hidden
+const MODEL_HIDDEN_BELOW: f32 = 0.3;
+
+/// The visible character model of the player
+#[derive(Component)]
+pub struct PlayerModel;
+
+/// Smoothstep easing, slow at both ends of the transition
+pub fn ease(t: f32) -> f32 {
+	let t = t.clamp(0.0, 1.0);
+	t * t * (3.0 - 2.0 * t)
+}
+
+/// Moves the perspective blend towards the chosen view, toggling mid way turns around from where the blend is
+pub fn update_perspective_blend(time: Res<Time>, mut camera_query: Query<&mut CameraController>) {
+	for mut camera_controller in camera_query.iter_mut() {
+		let target = if camera_controller.is_first_person { 0.0 } else { 1.0 };
+
+		if camera_controller.perspective_blend == target {
+			continue;
+		}
+
+		let step = if camera_controller.perspective_transition_time > 0.0 {
+			time.delta_secs() / camera_controller.perspective_transition_time
+		} else {
+			1.0
+		};
+
+		let blend = camera_controller.perspective_blend;
+		camera_controller.perspective_blend = if target > blend { (blend + step).min(target) } else { (blend - step).max(target) };
+	}
+}
+
+/// Hides the model while the camera is in first person
+pub fn update_model_visibility(camera_query: Query<&CameraController>, mut model_query: Query<&mut Visibility, With<PlayerModel>>) {
+	let Ok(camera_controller) = camera_query.get_single() else {
+		return;
+	};
+
+	let visibility = if ease(camera_controller.perspective_blend) < MODEL_HIDDEN_BELOW { Visibility::Hidden } else { Visibility::Inherited };
+
+	for mut model_visibility in model_query.iter_mut() {
+		if *model_visibility != visibility {
+			*model_visibility = visibility;
+		}
+	}
+}
@@ -0,0 +1,94 @@
+use bevy::{log, prelude::*};
+use crate::level::SpawnPoints;
+use crate::physics::{KinematicCharacterController, QueryFilter};
+use super::Player;
+use super::camera::CameraController;
+use super::controller::PlayerControllerState;
+use super::health::PlayerRespawned;
+
+/// Seconds to wait for the ground under the spawn point before using the spawn point's own height
+const GROUND_TIMEOUT: f32 = 5.0;
+
+/// Added while the player waits to be put at a spawn point, it doesn't move until then
+#[derive(Component, Default)]
+pub struct AwaitingSpawn {
+	/// Sends `PlayerRespawned` once placed
+	pub respawn: bool,
+	waited: f32,
+}
+
+impl AwaitingSpawn {
+	pub fn respawn() -> Self {
+		Self { respawn: true, waited: 0.0 }
+	}
+}
+
+/// Sent whenever the player is put at a spawn point, on the first spawn and after respawning
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerSpawned {
+	pub entity: Entity,
+	pub position: Vec3,
+}
+
+/// Puts waiting players on the ground at the selected spawn point, facing the spawn point's direction
+pub fn place_at_spawn_point(
+	mut commands: Commands,
+	time: Res<Time>,
+	spawn_points: SpawnPoints,
+	mut input: ResMut<PlayerControllerState>,
+	mut player_query: Query<(Entity, &mut AwaitingSpawn, &mut Player, &mut Transform, &mut KinematicCharacterController)>,
+	mut camera_query: Query<&mut CameraController>,
+	mut spawned: EventWriter<PlayerSpawned>,
+	mut respawned: EventWriter<PlayerRespawned>,
+) {
+	for (entity, mut awaiting, mut player, mut transform, mut controller) in player_query.iter_mut() {
+		awaiting.waited += time.delta_secs();
+
+		let Some((point, point_transform)) = spawn_points.selected() else {
+			if awaiting.waited >= GROUND_TIMEOUT {
+				log::error!("Spawn: the level has no spawn point");
+				commands.entity(entity).remove::<AwaitingSpawn>();
+			}
+			continue;
+		};
+
+		let point_position = point_transform.translation();
+
+		// Terrain chunks stream in around the player, so it waits at the spawn point for the ground there to load
+		transform.translation = point_position;
+
+		let filter = QueryFilter::default().exclude_sensors().exclude_collider(entity);
+		let ground = match spawn_points.ground_height(point_position, player.height(), filter) {
+			Some(ground) => ground,
+			None if awaiting.waited >= GROUND_TIMEOUT => {
+				log::warn!("Spawn: no ground found under {}, using its height", point.name);
+				point_position.y
+			},
+			None => continue,
+		};
+
+		// The player root is the center of the capsule
+		let position = Vec3::new(point_position.x, ground + player.height() / 2.0, point_position.z);
+		let (yaw, _, _) = point_transform.rotation().to_euler(EulerRot::YXZ);
+
+		transform.translation = position;
+		transform.rotation = Quat::from_rotation_y(yaw);
+		controller.translation = None;
+		player.velocity = Vec3::ZERO;
+		player.grounded = false;
+		input.position = position;
+
+		for mut camera_controller in camera_query.iter_mut() {
+			camera_controller.rotation = Vec2::new(0.0, yaw.to_degrees());
+			camera_controller.rotation_copy = None;
+		}
+
+		log::info!("Spawned at {}", point.name);
+		commands.entity(entity).remove::<AwaitingSpawn>();
+		spawned.send(PlayerSpawned { entity, position });
+
+		if awaiting.respawn {
+			respawned.send(PlayerRespawned { entity, position });
+		}
+	}
+}
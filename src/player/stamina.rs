@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use super::controller::PlayerControllerState;
+
+/// Sprinting drains stamina, it comes back after a short rest
+#[derive(Component)]
+pub struct Stamina {
+	pub current: f32,
+	pub max: f32,
+	/// Stamina used per second of sprinting
+	pub drain_rate: f32,
+	/// Stamina regained per second once resting
+	pub regen_rate: f32,
+	/// Seconds after sprinting stops before stamina starts coming back
+	pub regen_delay: f32,
+	/// Fraction of the max stamina needed to sprint again after running out
+	pub recover_fraction: f32,
+	pub regen_timer: f32,
+	/// Set when stamina runs out, sprinting is locked until it recovers
+	pub exhausted: bool,
+}
+
+impl Default for Stamina {
+	fn default() -> Self {
+		Stamina {
+			current: 100.0,
+			max: 100.0,
+			drain_rate: 20.0,
+			regen_rate: 15.0,
+			regen_delay: 1.0,
+			recover_fraction: 0.3,
+			regen_timer: 0.0,
+			exhausted: false,
+		}
+	}
+}
+
+impl Stamina {
+	/// Whether there is stamina left to sprint with
+	pub fn can_sprint(&self) -> bool {
+		!self.exhausted && self.current > 0.0
+	}
+
+	/// The stamina left from 0 to 1
+	pub fn fraction(&self) -> f32 {
+		if self.max > 0.0 { (self.current / self.max).clamp(0.0, 1.0) } else { 0.0 }
+	}
+}
+
+/// Drains stamina while sprinting and regenerates it after the delay
+pub fn update_stamina(time: Res<Time>, input: Res<PlayerControllerState>, mut stamina_query: Query<&mut Stamina>) {
+	let delta = time.delta_secs();
+
+	for mut stamina in stamina_query.iter_mut() {
+		if input.sprinting {
+			stamina.current = (stamina.current - stamina.drain_rate * delta).max(0.0);
+			stamina.regen_timer = stamina.regen_delay;
+
+			if stamina.current <= 0.0 {
+				stamina.exhausted = true;
+			}
+			continue;
+		}
+
+		if stamina.regen_timer > 0.0 {
+			stamina.regen_timer = (stamina.regen_timer - delta).max(0.0);
+			continue;
+		}
+
+		stamina.current = (stamina.current + stamina.regen_rate * delta).min(stamina.max);
+
+		if stamina.exhausted && stamina.current >= stamina.max * stamina.recover_fraction {
+			stamina.exhausted = false;
+		}
+	}
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+use crate::physics::{Collider, QueryFilter, SpatialQuery};
+use super::Player;
+use super::controller::PlayerControllerState;
+use super::perspective::PlayerModel;
+use super::spawn::AwaitingSpawn;
+
+/// Keeps this much space under the overlap test so the floor doesn't count as a ceiling
+const GROUND_CLEARANCE: f32 = 0.05;
+
+/// How the player holds itself, lower stances are slower and fit under lower ceilings
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Stance {
+	#[default]
+	Standing,
+	Crouching,
+	Prone,
+}
+
+impl Stance {
+	/// Name shown in the diagnostics
+	pub fn name(self) -> &'static str {
+		match self {
+			Stance::Standing => "Standing",
+			Stance::Crouching => "Crouching",
+			Stance::Prone => "Prone",
+		}
+	}
+}
+
+/// Switches to the wanted stance, resizing the collider around the feet, and stays put while the new stance doesn't fit
+///
+/// The body is moved to the new center by `update_movement` through the character controller
+pub fn update_stance(
+	spatial_query: SpatialQuery,
+	mut input: ResMut<PlayerControllerState>,
+	mut player_query: Query<(Entity, &mut Player, &Transform, &mut Collider, &Children), (Without<PlayerModel>, Without<AwaitingSpawn>)>,
+	mut model_query: Query<&mut Transform, With<PlayerModel>>,
+) {
+	for (entity, mut player, transform, mut collider, children) in player_query.iter_mut() {
+		let wanted = input.wanted_stance;
+
+		if wanted != player.stance {
+			let feet = transform.translation.y - player.height() / 2.0;
+			let height = player.stance_height(wanted);
+
+			// Getting taller can hit a ceiling and lying down can hit a wall
+			let blocked = (height > player.height() || wanted == Stance::Prone) && {
+				let clearance = player.stance_collider(wanted, player.capsule_radius * 0.95);
+				let center = Vec3::new(transform.translation.x, feet + height / 2.0 + GROUND_CLEARANCE, transform.translation.z);
+				let filter = QueryFilter::default().exclude_sensors().exclude_collider(entity);
+				spatial_query.overlap(&clearance, center, transform.rotation, filter).is_some()
+			};
+
+			if !blocked {
+				// The center moves by half the change in height so the feet stay on the ground
+				player.stance_shift = (height - player.height()) / 2.0;
+
+				player.stance = wanted;
+				*collider = player.collider();
+
+				for child in children.iter() {
+					if let Ok(mut model_transform) = model_query.get_mut(*child) {
+						*model_transform = model_transform_in(&player, wanted);
+					}
+				}
+			}
+		}
+
+		input.stance = player.stance;
+	}
+}
+
+/// Where the model sits under the player root, its feet are at its origin and it lies face down along the body when prone
+fn model_transform_in(player: &Player, stance: Stance) -> Transform {
+	let height = player.stance_height(stance);
+
+	match stance {
+		Stance::Prone => Transform::from_xyz(0.0, -height / 2.0, player.prone_half_length + player.capsule_radius)
+			.with_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+		_ => Transform::from_xyz(0.0, -height / 2.0, 0.0),
+	}
+}
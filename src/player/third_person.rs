@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use crate::physics::{Collider, QueryFilter, SpatialQuery};
+use super::Player;
+use super::spawn::PlayerSpawned;
+use super::camera::CameraController;
+use super::perspective::ease;
+
+/// Spring arm that keeps the third person camera behind the player without passing through walls
+#[derive(Component)]
+pub struct ThirdPersonRig {
+	/// Point the camera orbits around, relative to the center of the player
+	pub pivot_offset: Vec3,
+	/// Moves the camera over the shoulder, in camera space
+	pub shoulder_offset: Vec3,
+	/// Arm length before zooming
+	pub arm_length: f32,
+	/// Radius of the sphere swept along the arm, keeps the near plane out of walls
+	pub collision_radius: f32,
+	/// How quickly the camera follows the player, higher is snappier
+	pub position_damping: f32,
+	/// How quickly the camera turns towards the look direction
+	pub rotation_damping: f32,
+	/// How quickly the arm grows back after an obstacle is gone, it always shrinks instantly
+	pub arm_damping: f32,
+	/// How quickly the first person eye follows stance changes
+	pub eye_damping: f32,
+	pivot: Vec3,
+	rotation: Quat,
+	distance: f32,
+	/// Eased height of the first person eye above the feet
+	eye_height: Option<f32>,
+	/// Jumps straight to the target on the next update instead of easing
+	snap: bool,
+}
+
+impl Default for ThirdPersonRig {
+	fn default() -> Self {
+		ThirdPersonRig {
+			pivot_offset: Vec3::new(0.0, 0.7, 0.0),
+			shoulder_offset: Vec3::new(0.5, 0.2, 0.0),
+			arm_length: 2.5,
+			collision_radius: 0.2,
+			position_damping: 15.0,
+			rotation_damping: 25.0,
+			arm_damping: 6.0,
+			eye_damping: 12.0,
+			pivot: Vec3::ZERO,
+			rotation: Quat::IDENTITY,
+			distance: 0.0,
+			eye_height: None,
+			snap: true,
+		}
+	}
+}
+
+/// Fraction to move towards a target this frame for an exponential ease that doesn't depend on the frame rate
+pub fn damp(rate: f32, delta: f32) -> f32 {
+	1.0 - (-rate * delta).exp()
+}
+
+/// Jumps to the player after spawning instead of flying across the level
+pub fn snap_on_spawn(mut spawned: EventReader<PlayerSpawned>, player_query: Query<(), With<Player>>, mut rig_query: Query<&mut ThirdPersonRig>) {
+	let Some(spawn) = spawned.read().filter(|spawn| player_query.contains(spawn.entity)).last() else {
+		return;
+	};
+
+	for mut rig in rig_query.iter_mut() {
+		rig.pivot = spawn.position + rig.pivot_offset;
+		rig.snap = true;
+		rig.eye_height = None;
+	}
+}
+
+/// Places the third person camera at the end of the spring arm
+///
+/// The player has no parent so its `Transform` is already in world space, its `GlobalTransform` would be a frame behind here
+pub fn update_third_person_camera(
+	time: Res<Time>,
+	spatial_query: SpatialQuery,
+	player_query: Query<(Entity, &Transform, &Player), Without<ThirdPersonRig>>,
+	mut camera_query: Query<(&mut CameraController, &mut ThirdPersonRig, &mut Transform)>,
+) {
+	let Ok((player_entity, player_transform, player)) = player_query.get_single() else {
+		return;
+	};
+
+	let delta = time.delta_secs();
+
+	for (mut camera_controller, mut rig, mut transform) in camera_query.iter_mut() {
+		let player_position = player_transform.translation;
+
+		// The eye eases to the new height when the stance changes, the feet stay put so they're the reference
+		let eye_height = match rig.eye_height {
+			Some(eye_height) => eye_height.lerp(player.eye_height(), damp(rig.eye_damping, delta)),
+			None => player.eye_height(),
+		};
+		rig.eye_height = Some(eye_height);
+		let eye_position = player_position + Vec3::Y * (eye_height - player.height() / 2.0);
+
+		// Fully in first person the camera sits at the eye and the camera controller turns it
+		if camera_controller.perspective_blend <= 0.0 {
+			rig.snap = true;
+			transform.translation = eye_position;
+			continue;
+		}
+
+		camera_controller.zoom.smooth(delta);
+
+		let target_pivot = player_position + rig.pivot_offset;
+		let target_rotation = camera_controller.target_rotation();
+		let arm_length = rig.arm_length + camera_controller.zoom.current;
+
+		if rig.snap {
+			rig.pivot = target_pivot;
+			rig.rotation = target_rotation;
+			rig.distance = arm_length;
+			rig.snap = false;
+		} else {
+			rig.pivot = rig.pivot.lerp(target_pivot, damp(rig.position_damping, delta));
+			rig.rotation = rig.rotation.slerp(target_rotation, damp(rig.rotation_damping, delta));
+		}
+
+		// The arm goes from the pivot to the shoulder position pushed back along the view
+		let arm = rig.rotation * (rig.shoulder_offset + Vec3::Z * arm_length);
+		let length = arm.length();
+		let Ok(direction) = Dir3::new(arm) else {
+			continue;
+		};
+
+		let filter = QueryFilter::default().exclude_sensors().exclude_collider(player_entity);
+		let clear_length = spatial_query
+			.shape_cast(&Collider::ball(rig.collision_radius), rig.pivot, Quat::IDENTITY, direction, length, filter)
+			.map_or(length, |(_, distance)| distance);
+
+		// Pull in right away so the camera never clips, ease back out to avoid popping
+		rig.distance = if clear_length < rig.distance {
+			clear_length
+		} else {
+			rig.distance.lerp(clear_length, damp(rig.arm_damping, delta))
+		};
+
+		let rig_translation = rig.pivot + direction * rig.distance;
+
+		// Blend from the first person eye while switching views
+		let blend = ease(camera_controller.perspective_blend);
+		transform.translation = eye_position.lerp(rig_translation, blend);
+		transform.rotation = target_rotation.slerp(rig.rotation, blend);
+	}
+}
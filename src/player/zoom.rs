@@ -1,17 +1,28 @@
 use bevy::{input::mouse::MouseWheel, prelude::*};
 use super::camera::CameraController;
+use super::third_person::damp;
 
 /// Sets the zoom bounds (min & max)
 pub struct Zoom {
 	pub min: f32,
 	pub max: f32,
+	/// The zoom the scroll wheel asked for
 	pub radius: f32,
-
+	/// The zoom the camera uses, eases towards the radius
+	pub current: f32,
+	/// How quickly the current zoom catches up with the radius
+	pub damping: f32,
 }
 
 impl Zoom {
 	pub fn new(min: f32, max: f32) -> Self {
-		Self {min, max, radius: (min + max) / 2.0}
+		let radius = (min + max) / 2.0;
+		Self {min, max, radius, current: radius, damping: 10.0}
+	}
+
+	/// Eases the current zoom towards the radius
+	pub fn smooth(&mut self, delta: f32) {
+		self.current = self.current.lerp(self.radius, damp(self.damping, delta));
 	}
 }
 
@@ -0,0 +1,161 @@
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use crate::input::{Action, Binding};
+use super::{AudioSettings, CameraSettings, ControlSettings, DisplaySettings, GraphicsSettings, Settings, SETTINGS_VERSION};
+
+/// Version 1 had a ToggleCursor action, the cursor now follows the game state
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SettingsV1 {
+	display: DisplaySettings,
+	camera: CameraSettings,
+	controls: ControlSettingsV1,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct ControlSettingsV1 {
+	bindings: HashMap<ActionV1, Vec<Binding>>,
+	stick_deadzone: f32,
+	look_response_curve: f32,
+}
+
+impl Default for ControlSettingsV1 {
+	fn default() -> Self {
+		let controls = ControlSettings::default();
+
+		Self {
+			bindings: HashMap::default(),
+			stick_deadzone: controls.stick_deadzone,
+			look_response_curve: controls.look_response_curve,
+		}
+	}
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+enum ActionV1 {
+	MoveForward,
+	MoveBack,
+	MoveLeft,
+	MoveRight,
+	Sprint,
+	Jump,
+	MainMenu,
+	ToggleView,
+	FreeLook,
+	Inventory,
+	ToggleCursor,
+}
+
+impl ActionV1 {
+	fn migrate(self) -> Option<Action> {
+		match self {
+			ActionV1::MoveForward => Some(Action::MoveForward),
+			ActionV1::MoveBack => Some(Action::MoveBack),
+			ActionV1::MoveLeft => Some(Action::MoveLeft),
+			ActionV1::MoveRight => Some(Action::MoveRight),
+			ActionV1::Sprint => Some(Action::Sprint),
+			ActionV1::Jump => Some(Action::Jump),
+			ActionV1::MainMenu => Some(Action::MainMenu),
+			ActionV1::ToggleView => Some(Action::ToggleView),
+			ActionV1::FreeLook => Some(Action::FreeLook),
+			ActionV1::Inventory => Some(Action::Inventory),
+			ActionV1::ToggleCursor => None,
+		}
+	}
+}
+
+impl From<SettingsV1> for Settings {
+	fn from(old: SettingsV1) -> Self {
+		let mut controls = ControlSettings {
+			stick_deadzone: old.controls.stick_deadzone,
+			look_response_curve: old.controls.look_response_curve,
+			..ControlSettings::default()
+		};
+		controls.bindings.extend(old.controls.bindings.into_iter()
+			.filter_map(|(action, bindings)| action.migrate().map(|action| (action, bindings))));
+
+		Settings {
+			version: SETTINGS_VERSION,
+			display: old.display,
+			graphics: GraphicsSettings::default(),
+			audio: AudioSettings::default(),
+			camera: old.camera,
+			controls,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use bevy::prelude::*;
+	use crate::input::{Action, Binding};
+	use crate::settings::{ControlSettings, GraphicsSettings, Settings, SETTINGS_VERSION};
+
+	#[test]
+	fn migrates_v1_and_drops_toggle_cursor() {
+		let v1 = "(
+			version: 1,
+			camera: (fov: 90.0),
+			controls: (
+				bindings: {
+					Jump: [Key(KeyF)],
+					ToggleCursor: [Key(KeyC)],
+				},
+				stick_deadzone: 0.3,
+			),
+		)";
+
+		let settings = Settings::parse(v1).unwrap();
+
+		assert_eq!(settings.version, SETTINGS_VERSION);
+		assert_eq!(settings.camera.fov, 90.0);
+		assert_eq!(settings.graphics, GraphicsSettings::default());
+		assert_eq!(settings.controls.stick_deadzone, 0.3);
+		assert_eq!(settings.controls.bindings[&Action::Jump], vec![Binding::Key(KeyCode::KeyF)]);
+
+		// ToggleCursor is gone and the actions that weren't rebound keep their defaults
+		let defaults = ControlSettings::default();
+		assert_eq!(settings.controls.bindings.len(), defaults.bindings.len());
+		assert_eq!(settings.controls.bindings[&Action::MoveForward], defaults.bindings[&Action::MoveForward]);
+	}
+
+	#[test]
+	fn reads_known_values_of_a_newer_version() {
+		let newer = format!("(
+			version: {},
+			camera: (fov: 80.0, added_later: true),
+			added_later: (value: 1),
+		)", SETTINGS_VERSION + 1);
+
+		let settings = Settings::parse(&newer).unwrap();
+
+		assert_eq!(settings.version, SETTINGS_VERSION);
+		assert_eq!(settings.camera.fov, 80.0);
+		assert_eq!(settings.controls, ControlSettings::default());
+	}
+
+	#[test]
+	fn rejects_unknown_values_of_a_newer_version() {
+		let newer = format!("(
+			version: {},
+			controls: (bindings: {{ AddedLater: [Key(KeyF)] }}),
+		)", SETTINGS_VERSION + 1);
+
+		assert!(Settings::parse(&newer).is_err());
+	}
+
+	#[test]
+	fn keeps_a_newer_file_it_could_not_read() {
+		let path = std::env::temp_dir().join(format!("turning_dawn_settings_test_{}.ron", std::process::id()));
+		let newer = format!("(version: {}, controls: (bindings: {{ AddedLater: [Key(KeyF)] }}))", SETTINGS_VERSION + 1);
+		std::fs::write(&path, &newer).unwrap();
+
+		let result = Settings::default().save_to(&path);
+		let contents = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		assert!(result.is_err());
+		assert_eq!(contents, newer);
+	}
+}
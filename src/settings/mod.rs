@@ -0,0 +1,291 @@
+use std::{fs, path::{Path, PathBuf}};
+use bevy::{log, prelude::*, utils::HashMap, window::PresentMode};
+use serde::{Deserialize, Serialize};
+use crate::input::{Action, Binding, InputMap};
+
+mod migration;
+
+const DIRECTORY: &str = "turning_dawn";
+const FILE_NAME: &str = "settings.ron";
+
+/// Version written to new settings files, bump it and add a migration when the layout changes
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// Loads the settings file at startup and saves it whenever the settings change
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+	fn build(&self, app: &mut App) {
+		// Loaded right away so startup systems of every plugin can read it
+		app.insert_resource(Settings::load())
+		.init_resource::<SettingsPreview>()
+		// Not on the first frame, the file was just read and has nothing new
+		.add_systems(Update, save_settings.run_if(resource_changed::<Settings>.and(not(resource_added::<Settings>))));
+	}
+}
+
+/// Every user tunable value, modules apply the parts they own when this changes
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+	pub version: u32,
+	pub display: DisplaySettings,
+	pub graphics: GraphicsSettings,
+	pub audio: AudioSettings,
+	pub camera: CameraSettings,
+	pub controls: ControlSettings,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			version: SETTINGS_VERSION,
+			display: DisplaySettings::default(),
+			graphics: GraphicsSettings::default(),
+			audio: AudioSettings::default(),
+			camera: CameraSettings::default(),
+			controls: ControlSettings::default(),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct DisplaySettings {
+	pub mode: DisplayMode,
+	/// Resolution in physical pixels, borderless always uses the size of the monitor
+	pub width: f32,
+	pub height: f32,
+	/// Name of the monitor to use, the primary monitor when missing or not connected
+	pub monitor: Option<String>,
+	pub present_mode: PresentMode,
+	/// Highest frame rate, 0 doesn't limit it
+	pub max_fps: u32,
+}
+
+impl Default for DisplaySettings {
+	fn default() -> Self {
+		Self {
+			mode: DisplayMode::Windowed,
+			width: 1920.0,
+			height: 1080.0,
+			monitor: None,
+			present_mode: PresentMode::AutoVsync,
+			max_fps: 0,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DisplayMode {
+	Windowed,
+	Borderless,
+	/// Exclusive fullscreen using the video mode closest to the resolution
+	Fullscreen,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct GraphicsSettings {
+	pub shadows: bool,
+}
+
+impl Default for GraphicsSettings {
+	fn default() -> Self {
+		Self { shadows: true }
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct AudioSettings {
+	/// Volume of every sound, between 0 and 1
+	pub master_volume: f32,
+}
+
+impl Default for AudioSettings {
+	fn default() -> Self {
+		Self { master_volume: 1.0 }
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct CameraSettings {
+	/// Vertical field of view in degrees
+	pub fov: f32,
+	pub sensitivity: f32,
+	pub zoom_sensitivity: f32,
+	/// How far up or down the camera can look in degrees
+	pub rotation_lock: f32,
+	/// Right stick turn speed in degrees per second at full deflection
+	pub gamepad_sensitivity: f32,
+	pub invert_gamepad_y: bool,
+}
+
+impl Default for CameraSettings {
+	fn default() -> Self {
+		Self {
+			fov: 103.0,
+			sensitivity: 0.5,
+			zoom_sensitivity: 1.0,
+			rotation_lock: 88.0,
+			gamepad_sensitivity: 180.0,
+			invert_gamepad_y: false,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct ControlSettings {
+	/// Overrides the default bindings of the listed actions
+	pub bindings: HashMap<Action, Vec<Binding>>,
+	/// Stick deflection ignored around the center, between 0 and 1
+	pub stick_deadzone: f32,
+	/// Exponent of the right stick response curve, 1 is linear
+	pub look_response_curve: f32,
+}
+
+impl Default for ControlSettings {
+	fn default() -> Self {
+		let map = InputMap::default();
+
+		Self {
+			bindings: map.bindings,
+			stick_deadzone: map.stick_deadzone,
+			look_response_curve: map.look_response_curve,
+		}
+	}
+}
+
+/// Only reads the version so the right migration can be picked before parsing the rest
+#[derive(Deserialize)]
+struct VersionProbe {
+	#[serde(default)]
+	version: u32,
+}
+
+impl Settings {
+	/// Path of the settings file in the user's config directory
+	pub fn path() -> Option<PathBuf> {
+		dirs::config_dir().map(|dir| dir.join(DIRECTORY).join(FILE_NAME))
+	}
+
+	/// Loads the settings file, falling back to the defaults when it is missing or broken
+	pub fn load() -> Self {
+		let Some(path) = Self::path() else {
+			log::warn!("Settings: no config directory, using defaults");
+			return Self::default();
+		};
+
+		let Ok(contents) = fs::read_to_string(&path) else {
+			log::info!("Settings: no settings file at {}, using defaults", path.display());
+			return Self::default();
+		};
+
+		match Self::parse(&contents) {
+			Ok(settings) => settings,
+			Err(e) => {
+				log::error!("Settings: failed to read {}, using defaults: {}", path.display(), e);
+				Self::default()
+			}
+		}
+	}
+
+	/// Parses a settings file of any known version into the current layout
+	pub fn parse(contents: &str) -> Result<Self, ron::error::SpannedError> {
+		let probe: VersionProbe = ron::from_str(contents)?;
+
+		if probe.version > SETTINGS_VERSION {
+			// Serde skips unknown fields but an unknown enum variant, like a new action, fails the whole parse
+			log::warn!("Settings: file version {} is newer than {}, unknown values make it fall back to the defaults and it won't be saved over", probe.version, SETTINGS_VERSION);
+		}
+
+		let mut settings: Settings = match probe.version {
+			0 | 1 => ron::from_str::<migration::SettingsV1>(contents)?.into(),
+			_ => ron::from_str(contents)?,
+		};
+		settings.version = SETTINGS_VERSION;
+		Ok(settings)
+	}
+
+	/// Writes the settings file, creating the directory if needed
+	pub fn save(&self) -> Result<(), String> {
+		let path = Self::path().ok_or("no config directory")?;
+		self.save_to(&path)
+	}
+
+	/// Writes the settings to a file, unless that file is from a newer version whose values would be lost
+	pub fn save_to(&self, path: &Path) -> Result<(), String> {
+		let existing = fs::read_to_string(path).ok()
+			.and_then(|contents| ron::from_str::<VersionProbe>(&contents).ok());
+		if let Some(probe) = existing.filter(|probe| probe.version > SETTINGS_VERSION) {
+			return Err(format!("{} is from a newer version ({}), not saving over it", path.display(), probe.version));
+		}
+
+		if let Some(directory) = path.parent() {
+			fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+		}
+
+		let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+		fs::write(path, contents).map_err(|e| e.to_string())
+	}
+}
+
+/// Holds the settings from before the settings menu was opened so unapplied changes can be previewed and reverted
+#[derive(Resource, Default)]
+pub struct SettingsPreview {
+	original: Option<Settings>,
+}
+
+impl SettingsPreview {
+	/// Starts previewing, the settings file isn't written until the changes are applied
+	pub fn begin(&mut self, settings: &Settings) {
+		self.original = Some(settings.clone());
+	}
+
+	pub fn is_active(&self) -> bool {
+		self.original.is_some()
+	}
+
+	/// Whether the previewed settings differ from the applied ones
+	pub fn has_changes(&self, settings: &Settings) -> bool {
+		self.original.as_ref().is_some_and(|original| original != settings)
+	}
+
+	/// Keeps the previewed settings and writes them to disk
+	pub fn apply(&mut self, settings: &Settings) {
+		if let Err(e) = settings.save() {
+			log::error!("Settings: failed to save: {}", e);
+		}
+
+		if self.is_active() {
+			self.original = Some(settings.clone());
+		}
+	}
+
+	/// Restores the settings from before the preview
+	pub fn revert(&self, settings: &mut Settings) {
+		if let Some(original) = &self.original {
+			if original != settings {
+				*settings = original.clone();
+			}
+		}
+	}
+
+	/// Stops previewing, call revert or apply first
+	pub fn end(&mut self) {
+		self.original = None;
+	}
+}
+
+fn save_settings(settings: Res<Settings>, preview: Res<SettingsPreview>) {
+	if preview.is_active() {
+		return;
+	}
+
+	if let Err(e) = settings.save() {
+		log::error!("Settings: failed to save: {}", e);
+	}
+}
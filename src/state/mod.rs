@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use crate::input::{Action, ActionState};
+
+/// Owns the game state machine and the pause toggle
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+	fn build(&self, app: &mut App) {
+		app.init_state::<GameState>()
+		.add_sub_state::<MenuScreen>()
+		.enable_state_scoped_entities::<GameState>()
+		.enable_state_scoped_entities::<MenuScreen>()
+		.add_systems(Update, toggle_pause.run_if(in_state(GameState::InGame).or(in_state(MenuScreen::Root))));
+	}
+}
+
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameState {
+	#[default]
+	MainMenu,
+	InGame,
+	Paused,
+	/// Editing the level, toggled from the game
+	Editor,
+}
+
+/// The screen shown while a menu is open
+#[derive(SubStates, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[source(GameState = GameState::MainMenu | GameState::Paused)]
+pub enum MenuScreen {
+	#[default]
+	Root,
+	Settings,
+}
+
+/// Pauses or resumes the game when the main menu action is pressed
+fn toggle_pause(mut actions: ResMut<ActionState>, state: Res<State<GameState>>, mut next_state: ResMut<NextState<GameState>>) {
+	if !actions.just_pressed(Action::MainMenu) {
+		return;
+	}
+
+	match state.get() {
+		GameState::InGame => next_state.set(GameState::Paused),
+		GameState::Paused => next_state.set(GameState::InGame),
+		GameState::MainMenu | GameState::Editor => return,
+	}
+
+	actions.consume(Action::MainMenu);
+}
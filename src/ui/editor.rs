@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use crate::editor::{EditorCommand, EditorState, EditorTool};
+use crate::level::{LevelObject, LevelShape};
+use crate::state::GameState;
+use super::widgets::{spawn_row, spawn_slider, Slider};
+use super::{label, spawn_sized_button, OVERLAY_COLOR};
+
+/// Size of the primitives added from the panel
+const NEW_CUBOID_SIZE: Vec3 = Vec3::splat(2.0);
+const NEW_PLANE_SIZE: Vec2 = Vec2::splat(10.0);
+
+/// The level editor panel with the tools, new primitives, the selected object's color and saving
+pub struct EditorPanelPlugin;
+
+impl Plugin for EditorPanelPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(OnEnter(GameState::Editor), spawn_editor_panel)
+		.add_systems(Update, (
+			editor_buttons,
+			read_selected_color.run_if(resource_changed::<EditorState>),
+			write_selected_color,
+			update_status_text,
+		).chain().run_if(in_state(GameState::Editor)));
+	}
+}
+
+#[derive(Component, Clone, Copy)]
+enum EditorButton {
+	Tool(EditorTool),
+	Command(EditorCommand),
+}
+
+/// One channel of the selected object's color, 0 is red, 1 green and 2 blue
+#[derive(Component)]
+struct ColorSlider(usize);
+
+/// Shows the tool and the selected object
+#[derive(Component)]
+struct StatusText;
+
+fn spawn_editor_panel(mut commands: Commands) {
+	commands.spawn((
+		Node {
+			position_type: PositionType::Absolute,
+			top: Val::Px(12.),
+			right: Val::Px(12.),
+			width: Val::Px(400.),
+			flex_direction: FlexDirection::Column,
+			row_gap: Val::Px(8.),
+			padding: UiRect::all(Val::Px(12.)),
+			..default()
+		},
+		BackgroundColor(OVERLAY_COLOR),
+		// Keeps clicks on the panel from selecting what's behind it
+		Interaction::default(),
+		StateScoped(GameState::Editor),
+	)).with_children(|panel| {
+		panel.spawn((label(""), StatusText));
+
+		panel.spawn(Node { column_gap: Val::Px(8.), ..default() }).with_children(|row| {
+			for tool in [EditorTool::Move, EditorTool::Rotate, EditorTool::Scale] {
+				spawn_sized_button(row, tool.name(), 120., EditorButton::Tool(tool));
+			}
+		});
+
+		panel.spawn(Node { column_gap: Val::Px(8.), ..default() }).with_children(|row| {
+			spawn_sized_button(row, "Add Cuboid", 184., EditorButton::Command(EditorCommand::Spawn(LevelShape::Cuboid { size: NEW_CUBOID_SIZE })));
+			spawn_sized_button(row, "Add Plane", 184., EditorButton::Command(EditorCommand::Spawn(LevelShape::Plane { size: NEW_PLANE_SIZE })));
+		});
+
+		for (channel, name) in ["Red", "Green", "Blue"].into_iter().enumerate() {
+			spawn_row(panel, name, |row| {
+				spawn_slider(row, 1.0, ColorSlider(channel));
+			});
+		}
+
+		panel.spawn(Node { column_gap: Val::Px(8.), ..default() }).with_children(|row| {
+			spawn_sized_button(row, "Delete", 184., EditorButton::Command(EditorCommand::Delete));
+			spawn_sized_button(row, "Save", 184., EditorButton::Command(EditorCommand::Save));
+		});
+
+		panel.spawn(label("Hold right mouse to fly with WASD, Space and C\n1 2 3 pick the tool, F1 goes back to the game"));
+	});
+}
+
+fn editor_buttons(
+	buttons: Query<(&Interaction, &EditorButton), Changed<Interaction>>,
+	mut editor: ResMut<EditorState>,
+	mut commands: EventWriter<EditorCommand>,
+) {
+	for (interaction, button) in buttons.iter() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		match *button {
+			EditorButton::Tool(tool) => editor.tool = tool,
+			EditorButton::Command(command) => {
+				commands.send(command);
+			},
+		}
+	}
+}
+
+/// Moves the color sliders to the color of a newly selected object
+fn read_selected_color(
+	editor: Res<EditorState>,
+	objects: Query<&MeshMaterial3d<StandardMaterial>, With<LevelObject>>,
+	materials: Res<Assets<StandardMaterial>>,
+	mut sliders: Query<(&mut Slider, &ColorSlider)>,
+	mut last_selected: Local<Option<Entity>>,
+) {
+	if *last_selected == editor.selected {
+		return;
+	}
+	*last_selected = editor.selected;
+
+	let Some(material) = editor.selected
+		.and_then(|entity| objects.get(entity).ok())
+		.and_then(|material| materials.get(&material.0)) else {
+		return;
+	};
+
+	let color = material.base_color.to_srgba().to_f32_array_no_alpha();
+	for (mut slider, channel) in sliders.iter_mut() {
+		slider.value = color[channel.0];
+	}
+}
+
+/// Paints the selected object with the color sliders
+fn write_selected_color(
+	editor: Res<EditorState>,
+	sliders: Query<(Ref<Slider>, &ColorSlider)>,
+	objects: Query<&MeshMaterial3d<StandardMaterial>, With<LevelObject>>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	if !sliders.iter().any(|(slider, _)| slider.is_changed() && !slider.is_added()) {
+		return;
+	}
+
+	let Some(material) = editor.selected
+		.and_then(|entity| objects.get(entity).ok())
+		.and_then(|material| materials.get_mut(&material.0)) else {
+		return;
+	};
+
+	let mut color = material.base_color.to_srgba();
+	for (slider, channel) in sliders.iter() {
+		match channel.0 {
+			0 => color.red = slider.value,
+			1 => color.green = slider.value,
+			_ => color.blue = slider.value,
+		}
+	}
+	material.base_color = color.into();
+}
+
+fn update_status_text(editor: Res<EditorState>, names: Query<&Name>, mut texts: Query<&mut Text, With<StatusText>>) {
+	let selected = editor.selected
+		.and_then(|entity| names.get(entity).ok())
+		.map_or("Nothing", |name| name.as_str());
+	let status = format!("Tool: {}\nSelected: {}", editor.tool.name(), selected);
+
+	for mut text in texts.iter_mut() {
+		if text.0 != status {
+			text.0 = status.clone();
+		}
+	}
+}
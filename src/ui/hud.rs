@@ -0,0 +1,227 @@
+use bevy::{log, prelude::*};
+use crate::player::{DamageCause, Health, PlayerDamaged, PlayerDied, PlayerRespawned, Stamina};
+use crate::player::controller::PlayerController;
+use crate::state::GameState;
+use crate::window::{Cursor, CursorLockChanged};
+use super::{label, BUTTON_COLOR};
+
+const STAMINA_COLOR: Color = Color::srgb(0.85, 0.75, 0.3);
+const STAMINA_EXHAUSTED_COLOR: Color = Color::srgb(0.75, 0.3, 0.25);
+const HEALTH_COLOR: Color = Color::srgb(0.7, 0.2, 0.2);
+/// Red of the screen flash when taking damage
+const DAMAGE_FLASH_COLOR: Color = Color::srgba(0.6, 0.0, 0.0, 0.0);
+/// How quickly the damage flash fades, in alpha per second
+const DAMAGE_FLASH_FADE: f32 = 1.5;
+
+/// Overlays shown while playing
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(OnEnter(GameState::InGame), spawn_hud)
+		.add_systems(Update, (
+			show_cursor_hint,
+			update_stamina_bar,
+			update_health_bar,
+			show_death_message,
+			flash_damage,
+		).run_if(in_state(GameState::InGame)));
+	}
+}
+
+/// Tells the player how to get the mouse back after alt tabbing
+#[derive(Component)]
+struct CursorHint;
+
+/// The filled part of the stamina bar
+#[derive(Component)]
+struct StaminaFill;
+
+/// The filled part of the health bar
+#[derive(Component)]
+struct HealthFill;
+
+/// Shown while the player is dead and waiting to respawn
+#[derive(Component)]
+struct DeathMessage;
+
+/// Full screen tint that flashes when the player gets hurt
+#[derive(Component)]
+struct DamageFlash;
+
+fn spawn_hud(mut commands: Commands) {
+	commands.spawn((
+		Node {
+			width: Val::Percent(100.),
+			height: Val::Percent(100.),
+			justify_content: JustifyContent::Center,
+			align_items: AlignItems::Center,
+			..default()
+		},
+		StateScoped(GameState::InGame),
+	)).with_children(|parent| {
+		parent.spawn((label("Click to capture the mouse"), CursorHint, Visibility::Hidden));
+	});
+
+	commands.spawn((
+		Node {
+			position_type: PositionType::Absolute,
+			width: Val::Percent(100.),
+			height: Val::Percent(100.),
+			justify_content: JustifyContent::Center,
+			align_items: AlignItems::Center,
+			..default()
+		},
+		BackgroundColor(DAMAGE_FLASH_COLOR),
+		DamageFlash,
+		StateScoped(GameState::InGame),
+	)).with_children(|parent| {
+		parent.spawn((label(""), DeathMessage, Visibility::Hidden));
+	});
+
+	commands.spawn((
+		Node {
+			position_type: PositionType::Absolute,
+			bottom: Val::Px(48.),
+			left: Val::Percent(50.),
+			width: Val::Px(240.),
+			height: Val::Px(8.),
+			margin: UiRect::left(Val::Px(-120.)),
+			..default()
+		},
+		BackgroundColor(BUTTON_COLOR),
+		StateScoped(GameState::InGame),
+	)).with_children(|bar| {
+		bar.spawn((
+			Node {
+				width: Val::Percent(100.),
+				height: Val::Percent(100.),
+				..default()
+			},
+			BackgroundColor(HEALTH_COLOR),
+			HealthFill,
+		));
+	});
+
+	commands.spawn((
+		Node {
+			position_type: PositionType::Absolute,
+			bottom: Val::Px(32.),
+			left: Val::Percent(50.),
+			width: Val::Px(240.),
+			height: Val::Px(8.),
+			margin: UiRect::left(Val::Px(-120.)),
+			..default()
+		},
+		BackgroundColor(BUTTON_COLOR),
+		StateScoped(GameState::InGame),
+	)).with_children(|bar| {
+		bar.spawn((
+			Node {
+				width: Val::Percent(100.),
+				height: Val::Percent(100.),
+				..default()
+			},
+			BackgroundColor(STAMINA_COLOR),
+			StaminaFill,
+		));
+	});
+}
+
+/// Fills the bar with the stamina left and turns it red while sprinting is locked
+fn update_stamina_bar(stamina_query: Query<&Stamina, Changed<Stamina>>, mut fills: Query<(&mut Node, &mut BackgroundColor), With<StaminaFill>>) {
+	let Ok(stamina) = stamina_query.get_single() else {
+		return;
+	};
+
+	for (mut node, mut color) in fills.iter_mut() {
+		node.width = Val::Percent(stamina.fraction() * 100.);
+		color.0 = if stamina.exhausted { STAMINA_EXHAUSTED_COLOR } else { STAMINA_COLOR };
+	}
+}
+
+fn show_cursor_hint(mut events: EventReader<CursorLockChanged>, cursor: Res<Cursor>, mut hints: Query<(&mut Visibility, Ref<CursorHint>)>) {
+	let latest = events.read().last().map(|change| change.locked);
+
+	for (mut visibility, hint) in hints.iter_mut() {
+		let locked = match latest {
+			Some(locked) => locked,
+			None if hint.is_added() => cursor.is_locked(),
+			None => continue,
+		};
+
+		*visibility = if locked { Visibility::Hidden } else { Visibility::Inherited };
+	}
+}
+
+fn update_health_bar(health_query: Query<Ref<Health>, With<PlayerController>>, mut fills: Query<(&mut Node, Ref<HealthFill>)>) {
+	let Ok(health) = health_query.get_single() else {
+		return;
+	};
+
+	for (mut node, fill) in fills.iter_mut() {
+		if health.is_changed() || fill.is_added() {
+			node.width = Val::Percent(health.fraction() * 100.);
+		}
+	}
+}
+
+fn death_text(cause: DamageCause) -> &'static str {
+	match cause {
+		DamageCause::Fall => "You fell to your death",
+		DamageCause::OutOfWorld => "You fell out of the world",
+	}
+}
+
+/// Shows why the player died until it respawns
+fn show_death_message(
+	mut died: EventReader<PlayerDied>,
+	mut respawned: EventReader<PlayerRespawned>,
+	player_query: Query<(Entity, &Health), With<PlayerController>>,
+	mut messages: Query<(&mut Text, &mut Visibility, Ref<DeathMessage>)>,
+) {
+	let Ok((player, health)) = player_query.get_single() else {
+		return;
+	};
+
+	let death = died.read().filter(|death| death.entity == player).last().map(|death| death.cause);
+	let respawn = respawned.read().filter(|respawn| respawn.entity == player).last().map(|respawn| respawn.position);
+
+	for (mut text, mut visibility, message) in messages.iter_mut() {
+		if let Some(position) = respawn {
+			log::info!("Respawned at {:.1}", position);
+			*visibility = Visibility::Hidden;
+		} else if let Some(cause) = death {
+			text.0 = death_text(cause).to_string();
+			*visibility = Visibility::Inherited;
+		} else if message.is_added() && health.is_dead() {
+			// The HUD is rebuilt after unpausing while dead
+			text.0 = "You died".to_string();
+			*visibility = Visibility::Inherited;
+		}
+	}
+}
+
+/// Tints the screen red in proportion to the damage taken and fades it out
+fn flash_damage(
+	time: Res<Time>,
+	mut damaged: EventReader<PlayerDamaged>,
+	player_query: Query<(Entity, &Health), With<PlayerController>>,
+	mut flashes: Query<&mut BackgroundColor, With<DamageFlash>>,
+) {
+	let Ok((player, health)) = player_query.get_single() else {
+		return;
+	};
+
+	let damage: f32 = damaged.read().filter(|damage| damage.entity == player).map(|damage| damage.amount).sum();
+
+	for mut color in flashes.iter_mut() {
+		let alpha = color.0.alpha();
+		let flashed = alpha + damage / health.max.max(1.0);
+		let faded = (flashed - DAMAGE_FLASH_FADE * time.delta_secs()).clamp(0.0, 0.6);
+
+		if faded != alpha {
+			color.0.set_alpha(faded);
+		}
+	}
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use crate::input::{Action, ActionState};
+use crate::state::{GameState, MenuScreen};
+use super::{screen_node, spawn_button, title};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(OnEnter(MenuScreen::Root), spawn_root_menu)
+		.add_systems(Update, (
+			menu_buttons.run_if(in_state(GameState::MainMenu).or(in_state(GameState::Paused))),
+			back_to_root.run_if(in_state(MenuScreen::Settings)),
+		));
+	}
+}
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+	Play,
+	Resume,
+	Settings,
+	Quit,
+}
+
+/// Spawns the main menu or the pause menu depending on the game state
+fn spawn_root_menu(mut commands: Commands, state: Res<State<GameState>>) {
+	let paused = *state.get() == GameState::Paused;
+
+	commands.spawn((screen_node(), StateScoped(MenuScreen::Root))).with_children(|parent| {
+		if paused {
+			parent.spawn(title("Paused"));
+			spawn_button(parent, "Resume", MenuButton::Resume);
+		} else {
+			parent.spawn(title("Turning Dawn"));
+			spawn_button(parent, "Play", MenuButton::Play);
+		}
+
+		spawn_button(parent, "Settings", MenuButton::Settings);
+		spawn_button(parent, "Quit", MenuButton::Quit);
+	});
+}
+
+fn menu_buttons(
+	buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+	mut next_state: ResMut<NextState<GameState>>,
+	mut next_screen: ResMut<NextState<MenuScreen>>,
+	mut exit: EventWriter<AppExit>,
+) {
+	for (interaction, button) in buttons.iter() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		match button {
+			MenuButton::Play | MenuButton::Resume => next_state.set(GameState::InGame),
+			MenuButton::Settings => next_screen.set(MenuScreen::Settings),
+			MenuButton::Quit => {
+				exit.send(AppExit::Success);
+			}
+		}
+	}
+}
+
+/// Leaves a sub screen when the main menu action is pressed
+pub(super) fn back_to_root(mut actions: ResMut<ActionState>, mut next_screen: ResMut<NextState<MenuScreen>>) {
+	if actions.just_pressed(Action::MainMenu) {
+		actions.consume(Action::MainMenu);
+		next_screen.set(MenuScreen::Root);
+	}
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+mod editor;
+mod hud;
+mod menu;
+mod settings;
+mod widgets;
+
+pub const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+pub const TEXT_SELECTED_COLOR: Color = Color::srgb(0.6, 0.9, 0.6);
+pub const BUTTON_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+pub const BUTTON_HOVERED_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+pub const BUTTON_PRESSED_COLOR: Color = Color::srgb(0.35, 0.45, 0.35);
+pub const OVERLAY_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+
+/// Menus and other screens built with Bevy UI
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_plugins((editor::EditorPanelPlugin, hud::HudPlugin, menu::MenuPlugin, settings::SettingsMenuPlugin, widgets::WidgetsPlugin))
+		.add_systems(Update, button_colors);
+	}
+}
+
+/// A full screen overlay that centers its children in a column
+pub fn screen_node() -> impl Bundle {
+	(
+		Node {
+			width: Val::Percent(100.),
+			height: Val::Percent(100.),
+			flex_direction: FlexDirection::Column,
+			align_items: AlignItems::Center,
+			justify_content: JustifyContent::Center,
+			row_gap: Val::Px(12.),
+			..default()
+		},
+		BackgroundColor(OVERLAY_COLOR),
+	)
+}
+
+/// A title text
+pub fn title(text: &str) -> impl Bundle {
+	(
+		Text::new(text),
+		TextFont { font_size: 48., ..default() },
+		TextColor(TEXT_COLOR),
+		Node { margin: UiRect::bottom(Val::Px(24.)), ..default() },
+	)
+}
+
+/// A plain label text
+pub fn label(text: &str) -> impl Bundle {
+	(
+		Text::new(text),
+		TextFont { font_size: 20., ..default() },
+		TextColor(TEXT_COLOR),
+	)
+}
+
+/// Spawns a button with a text label under a parent
+pub fn spawn_button(parent: &mut ChildBuilder, text: &str, marker: impl Bundle) -> Entity {
+	spawn_sized_button(parent, text, 260., marker)
+}
+
+/// Spawns a button of a given width with a text label under a parent
+pub fn spawn_sized_button(parent: &mut ChildBuilder, text: &str, width: f32, marker: impl Bundle) -> Entity {
+	parent.spawn((
+		Button,
+		Node {
+			width: Val::Px(width),
+			height: Val::Px(40.),
+			justify_content: JustifyContent::Center,
+			align_items: AlignItems::Center,
+			..default()
+		},
+		BackgroundColor(BUTTON_COLOR),
+		marker,
+	)).with_children(|button| {
+		button.spawn(label(text));
+	}).id()
+}
+
+/// Highlights buttons when hovered or pressed
+fn button_colors(mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>) {
+	for (interaction, mut color) in buttons.iter_mut() {
+		color.0 = match interaction {
+			Interaction::Pressed => BUTTON_PRESSED_COLOR,
+			Interaction::Hovered => BUTTON_HOVERED_COLOR,
+			Interaction::None => BUTTON_COLOR,
+		};
+	}
+}
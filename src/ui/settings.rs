@@ -0,0 +1,598 @@
+use bevy::{prelude::*, window::PresentMode};
+use crate::input::{Action, ActionState, Binding, InputMap};
+use crate::settings::{DisplayMode, Settings, SettingsPreview};
+use crate::state::MenuScreen;
+use crate::window::Monitors;
+use super::widgets::{spawn_dropdown, spawn_row, spawn_slider, spawn_toggle, Dropdown, Slider, Toggle};
+use super::{label, screen_node, spawn_sized_button, title, TEXT_COLOR, TEXT_SELECTED_COLOR};
+
+const DISPLAY_MODES: [(DisplayMode, &str); 3] = [
+	(DisplayMode::Windowed, "Windowed"),
+	(DisplayMode::Borderless, "Borderless"),
+	(DisplayMode::Fullscreen, "Fullscreen"),
+];
+
+const PRESENT_MODES: [(PresentMode, &str); 5] = [
+	(PresentMode::AutoVsync, "On"),
+	(PresentMode::AutoNoVsync, "Off"),
+	(PresentMode::FifoRelaxed, "Adaptive"),
+	(PresentMode::Mailbox, "Fast"),
+	(PresentMode::Immediate, "Immediate"),
+];
+
+/// Frame rate caps offered in the menu, 0 doesn't limit it
+const MAX_FPS: [u32; 7] = [0, 30, 60, 120, 144, 165, 240];
+
+/// The settings screen, changes are previewed live and only saved when applied
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<SettingsTab>()
+		.init_resource::<RebindCapture>()
+		.add_systems(OnEnter(MenuScreen::Settings), (begin_preview, spawn_settings_menu))
+		.add_systems(OnExit(MenuScreen::Settings), end_preview)
+		.add_systems(Update, (
+			capture_binding.before(settings_buttons).before(super::menu::back_to_root),
+			settings_buttons,
+			build_settings_content,
+			(write_sliders, write_toggles, write_choices),
+			sync_widgets.run_if(resource_changed::<Settings>),
+			(update_binding_text, update_tab_text, update_unapplied_text),
+		).chain().run_if(in_state(MenuScreen::Settings)));
+	}
+}
+
+/// The group of settings shown
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+enum SettingsTab {
+	#[default]
+	Graphics,
+	Audio,
+	Camera,
+	Controls,
+	Editor,
+}
+
+impl SettingsTab {
+	const ALL: [SettingsTab; 5] = [SettingsTab::Graphics, SettingsTab::Audio, SettingsTab::Camera, SettingsTab::Controls, SettingsTab::Editor];
+
+	fn name(self) -> &'static str {
+		match self {
+			SettingsTab::Graphics => "Graphics",
+			SettingsTab::Audio => "Audio",
+			SettingsTab::Camera => "Camera",
+			SettingsTab::Controls => "Controls",
+			SettingsTab::Editor => "Editor",
+		}
+	}
+}
+
+/// Which bindings of an action a rebind replaces
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BindingSlot {
+	KeyboardMouse,
+	Gamepad,
+}
+
+/// The binding waiting for an input, escape cancels it
+#[derive(Resource, Default)]
+struct RebindCapture(Option<(Action, BindingSlot)>);
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+	Tab(SettingsTab),
+	Rebind(Action, BindingSlot),
+	Apply,
+	Revert,
+	Back,
+}
+
+/// Holds the rows of the selected tab
+#[derive(Component)]
+struct SettingsContent;
+
+/// Tells the player the previewed settings aren't saved yet
+#[derive(Component)]
+struct UnappliedText;
+
+/// Shows the value of a slider
+#[derive(Component)]
+struct SliderValueText(SliderSetting);
+
+#[derive(Component, Clone, Copy)]
+enum SliderSetting {
+	MasterVolume,
+	Fov,
+	Sensitivity,
+	ZoomSensitivity,
+	RotationLock,
+	GamepadSensitivity,
+	StickDeadzone,
+	LookResponseCurve,
+}
+
+impl SliderSetting {
+	/// Minimum, maximum and step of the value
+	fn range(self) -> (f32, f32, f32) {
+		match self {
+			SliderSetting::MasterVolume => (0.0, 1.0, 0.05),
+			SliderSetting::Fov => (60.0, 120.0, 1.0),
+			SliderSetting::Sensitivity => (0.05, 2.0, 0.05),
+			SliderSetting::ZoomSensitivity => (0.1, 3.0, 0.1),
+			SliderSetting::RotationLock => (45.0, 89.0, 1.0),
+			SliderSetting::GamepadSensitivity => (30.0, 360.0, 10.0),
+			SliderSetting::StickDeadzone => (0.0, 0.5, 0.01),
+			SliderSetting::LookResponseCurve => (1.0, 3.0, 0.1),
+		}
+	}
+
+	fn get(self, settings: &Settings) -> f32 {
+		match self {
+			SliderSetting::MasterVolume => settings.audio.master_volume,
+			SliderSetting::Fov => settings.camera.fov,
+			SliderSetting::Sensitivity => settings.camera.sensitivity,
+			SliderSetting::ZoomSensitivity => settings.camera.zoom_sensitivity,
+			SliderSetting::RotationLock => settings.camera.rotation_lock,
+			SliderSetting::GamepadSensitivity => settings.camera.gamepad_sensitivity,
+			SliderSetting::StickDeadzone => settings.controls.stick_deadzone,
+			SliderSetting::LookResponseCurve => settings.controls.look_response_curve,
+		}
+	}
+
+	fn set(self, settings: &mut Settings, value: f32) {
+		match self {
+			SliderSetting::MasterVolume => settings.audio.master_volume = value,
+			SliderSetting::Fov => settings.camera.fov = value,
+			SliderSetting::Sensitivity => settings.camera.sensitivity = value,
+			SliderSetting::ZoomSensitivity => settings.camera.zoom_sensitivity = value,
+			SliderSetting::RotationLock => settings.camera.rotation_lock = value,
+			SliderSetting::GamepadSensitivity => settings.camera.gamepad_sensitivity = value,
+			SliderSetting::StickDeadzone => settings.controls.stick_deadzone = value,
+			SliderSetting::LookResponseCurve => settings.controls.look_response_curve = value,
+		}
+	}
+
+	fn format(self, value: f32) -> String {
+		match self {
+			SliderSetting::MasterVolume => format!("{:.0}%", value * 100.),
+			SliderSetting::Fov | SliderSetting::RotationLock => format!("{:.0}°", value),
+			SliderSetting::GamepadSensitivity => format!("{:.0}°/s", value),
+			_ => format!("{:.2}", value),
+		}
+	}
+
+	/// Position of the value on the slider between 0 and 1
+	fn normalized(self, settings: &Settings) -> f32 {
+		let (min, max, _) = self.range();
+		((self.get(settings) - min) / (max - min)).clamp(0.0, 1.0)
+	}
+
+	/// Value at a position on the slider, snapped to the step
+	fn from_normalized(self, position: f32) -> f32 {
+		let (min, max, step) = self.range();
+		let value = min + position.clamp(0.0, 1.0) * (max - min);
+		(((value - min) / step).round() * step + min).clamp(min, max)
+	}
+}
+
+#[derive(Component, Clone, Copy)]
+enum ToggleSetting {
+	Shadows,
+	InvertGamepadY,
+}
+
+impl ToggleSetting {
+	fn get(self, settings: &Settings) -> bool {
+		match self {
+			ToggleSetting::Shadows => settings.graphics.shadows,
+			ToggleSetting::InvertGamepadY => settings.camera.invert_gamepad_y,
+		}
+	}
+
+	fn set(self, settings: &mut Settings, on: bool) {
+		match self {
+			ToggleSetting::Shadows => settings.graphics.shadows = on,
+			ToggleSetting::InvertGamepadY => settings.camera.invert_gamepad_y = on,
+		}
+	}
+}
+
+#[derive(Component, Clone, Copy)]
+enum ChoiceSetting {
+	WindowMode,
+	Monitor,
+	Resolution,
+	PresentMode,
+	MaxFps,
+}
+
+impl ChoiceSetting {
+	fn options(self, settings: &Settings, monitors: &Monitors) -> Vec<String> {
+		match self {
+			ChoiceSetting::WindowMode => DISPLAY_MODES.iter().map(|(_, name)| name.to_string()).collect(),
+			ChoiceSetting::Monitor => {
+				let names = monitors.names();
+				if names.is_empty() { vec!["Primary".to_string()] } else { names }
+			}
+			ChoiceSetting::Resolution => monitors.resolutions(&settings.display).iter().map(|(width, height)| format!("{} x {}", width, height)).collect(),
+			ChoiceSetting::PresentMode => PRESENT_MODES.iter().map(|(_, name)| name.to_string()).collect(),
+			ChoiceSetting::MaxFps => max_fps_options(settings).iter()
+				.map(|fps| if *fps == 0 { "Unlimited".to_string() } else { fps.to_string() })
+				.collect(),
+		}
+	}
+
+	fn selected(self, settings: &Settings, monitors: &Monitors) -> usize {
+		let display = &settings.display;
+
+		match self {
+			ChoiceSetting::WindowMode => DISPLAY_MODES.iter().position(|(mode, _)| *mode == display.mode),
+			ChoiceSetting::Monitor => monitors.selected_index(display),
+			ChoiceSetting::Resolution => monitors.resolutions(display).iter().position(|resolution| *resolution == (display.width, display.height)),
+			ChoiceSetting::PresentMode => PRESENT_MODES.iter().position(|(mode, _)| *mode == display.present_mode),
+			ChoiceSetting::MaxFps => max_fps_options(settings).iter().position(|fps| *fps == display.max_fps),
+		}.unwrap_or_default()
+	}
+
+	fn select(self, settings: &mut Settings, monitors: &Monitors, index: usize) {
+		match self {
+			ChoiceSetting::WindowMode => {
+				if let Some((mode, _)) = DISPLAY_MODES.get(index) {
+					settings.display.mode = *mode;
+				}
+			}
+			ChoiceSetting::Monitor => settings.display.monitor = monitors.names().get(index).cloned(),
+			ChoiceSetting::Resolution => {
+				if let Some((width, height)) = monitors.resolutions(&settings.display).get(index) {
+					settings.display.width = *width;
+					settings.display.height = *height;
+				}
+			}
+			ChoiceSetting::PresentMode => {
+				if let Some((mode, _)) = PRESENT_MODES.get(index) {
+					settings.display.present_mode = *mode;
+				}
+			}
+			ChoiceSetting::MaxFps => {
+				if let Some(fps) = max_fps_options(settings).get(index) {
+					settings.display.max_fps = *fps;
+				}
+			}
+		}
+	}
+}
+
+/// The common frame rate caps plus the current one when it isn't in the list
+fn max_fps_options(settings: &Settings) -> Vec<u32> {
+	let mut options = MAX_FPS.to_vec();
+
+	if !options.contains(&settings.display.max_fps) {
+		options.push(settings.display.max_fps);
+	}
+
+	options
+}
+
+fn begin_preview(settings: Res<Settings>, mut preview: ResMut<SettingsPreview>, mut capture: ResMut<RebindCapture>) {
+	preview.begin(&settings);
+	capture.0 = None;
+}
+
+/// Leaving the screen drops the changes that weren't applied
+fn end_preview(mut settings: ResMut<Settings>, mut preview: ResMut<SettingsPreview>, mut capture: ResMut<RebindCapture>) {
+	preview.revert(&mut settings);
+	preview.end();
+	capture.0 = None;
+}
+
+fn spawn_settings_menu(mut commands: Commands) {
+	commands.spawn((screen_node(), StateScoped(MenuScreen::Settings))).with_children(|parent| {
+		parent.spawn(title("Settings"));
+
+		parent.spawn(Node { column_gap: Val::Px(8.), ..default() }).with_children(|tabs| {
+			for tab in SettingsTab::ALL {
+				spawn_sized_button(tabs, tab.name(), 160., SettingsButton::Tab(tab));
+			}
+		});
+
+		parent.spawn((
+			SettingsContent,
+			Node {
+				width: Val::Px(720.),
+				min_height: Val::Px(520.),
+				flex_direction: FlexDirection::Column,
+				row_gap: Val::Px(8.),
+				..default()
+			},
+		));
+
+		parent.spawn(Node { column_gap: Val::Px(8.), ..default() }).with_children(|buttons| {
+			spawn_sized_button(buttons, "Apply", 160., SettingsButton::Apply);
+			spawn_sized_button(buttons, "Revert", 160., SettingsButton::Revert);
+			spawn_sized_button(buttons, "Back", 160., SettingsButton::Back);
+		});
+
+		parent.spawn((label(""), UnappliedText));
+	});
+}
+
+/// Fills the content with the rows of the selected tab
+fn build_settings_content(
+	mut commands: Commands,
+	tab: Res<SettingsTab>,
+	settings: Res<Settings>,
+	monitors: Monitors,
+	content_query: Query<(Entity, Ref<SettingsContent>)>,
+) {
+	let Ok((entity, content)) = content_query.get_single() else {
+		return;
+	};
+
+	if !tab.is_changed() && !content.is_added() {
+		return;
+	}
+
+	commands.entity(entity).despawn_descendants().with_children(|parent| match *tab {
+		SettingsTab::Graphics => {
+			spawn_choice_row(parent, "Window Mode", ChoiceSetting::WindowMode, &settings, &monitors);
+			spawn_choice_row(parent, "Monitor", ChoiceSetting::Monitor, &settings, &monitors);
+			spawn_choice_row(parent, "Resolution", ChoiceSetting::Resolution, &settings, &monitors);
+			spawn_choice_row(parent, "VSync", ChoiceSetting::PresentMode, &settings, &monitors);
+			spawn_choice_row(parent, "Frame Rate Cap", ChoiceSetting::MaxFps, &settings, &monitors);
+			spawn_toggle_row(parent, "Shadows", ToggleSetting::Shadows, &settings);
+		}
+		SettingsTab::Audio => {
+			spawn_slider_row(parent, "Master Volume", SliderSetting::MasterVolume, &settings);
+		}
+		SettingsTab::Camera => {
+			spawn_slider_row(parent, "Field of View", SliderSetting::Fov, &settings);
+			spawn_slider_row(parent, "Mouse Sensitivity", SliderSetting::Sensitivity, &settings);
+			spawn_slider_row(parent, "Zoom Sensitivity", SliderSetting::ZoomSensitivity, &settings);
+			spawn_slider_row(parent, "Vertical Look Limit", SliderSetting::RotationLock, &settings);
+			spawn_slider_row(parent, "Gamepad Sensitivity", SliderSetting::GamepadSensitivity, &settings);
+			spawn_toggle_row(parent, "Invert Gamepad Y", ToggleSetting::InvertGamepadY, &settings);
+		}
+		SettingsTab::Controls => {
+			spawn_slider_row(parent, "Stick Deadzone", SliderSetting::StickDeadzone, &settings);
+			spawn_slider_row(parent, "Look Response Curve", SliderSetting::LookResponseCurve, &settings);
+
+			for action in Action::ALL.into_iter().filter(|action| !action.is_editor()) {
+				spawn_rebind_row(parent, action);
+			}
+		}
+		SettingsTab::Editor => {
+			for action in Action::ALL.into_iter().filter(|action| action.is_editor()) {
+				spawn_rebind_row(parent, action);
+			}
+		}
+	});
+}
+
+fn spawn_rebind_row(parent: &mut ChildBuilder, action: Action) {
+	spawn_row(parent, action.name(), |row| {
+		spawn_sized_button(row, "", 200., SettingsButton::Rebind(action, BindingSlot::KeyboardMouse));
+		spawn_sized_button(row, "", 200., SettingsButton::Rebind(action, BindingSlot::Gamepad));
+	});
+}
+
+fn spawn_slider_row(parent: &mut ChildBuilder, text: &str, setting: SliderSetting, settings: &Settings) {
+	spawn_row(parent, text, |row| {
+		row.spawn((label(&setting.format(setting.get(settings))), SliderValueText(setting)));
+		spawn_slider(row, setting.normalized(settings), setting);
+	});
+}
+
+fn spawn_toggle_row(parent: &mut ChildBuilder, text: &str, setting: ToggleSetting, settings: &Settings) {
+	spawn_row(parent, text, |row| {
+		spawn_toggle(row, setting.get(settings), setting);
+	});
+}
+
+fn spawn_choice_row(parent: &mut ChildBuilder, text: &str, setting: ChoiceSetting, settings: &Settings, monitors: &Monitors) {
+	spawn_row(parent, text, |row| {
+		spawn_dropdown(row, setting.options(settings, monitors), setting.selected(settings, monitors), setting);
+	});
+}
+
+fn settings_buttons(
+	buttons: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+	mut tab: ResMut<SettingsTab>,
+	mut capture: ResMut<RebindCapture>,
+	mut settings: ResMut<Settings>,
+	mut preview: ResMut<SettingsPreview>,
+	mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+	for (interaction, button) in buttons.iter() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		match *button {
+			SettingsButton::Tab(selected) => {
+				if *tab != selected {
+					*tab = selected;
+				}
+			}
+			SettingsButton::Rebind(action, slot) => capture.0 = Some((action, slot)),
+			SettingsButton::Apply => preview.apply(&settings),
+			SettingsButton::Revert => preview.revert(&mut settings),
+			SettingsButton::Back => next_screen.set(MenuScreen::Root),
+		}
+	}
+}
+
+/// Waits for a key, mouse button or gamepad button and binds it to the action being rebound
+fn capture_binding(
+	mut capture: ResMut<RebindCapture>,
+	keys: Res<ButtonInput<KeyCode>>,
+	mouse: Res<ButtonInput<MouseButton>>,
+	gamepads: Query<&Gamepad>,
+	map: Res<InputMap>,
+	mut actions: ResMut<ActionState>,
+	mut settings: ResMut<Settings>,
+) {
+	let Some((action, slot)) = capture.0 else {
+		return;
+	};
+
+	// The menu shouldn't close while an input is being captured
+	actions.consume(Action::MainMenu);
+
+	if keys.just_pressed(KeyCode::Escape) {
+		capture.0 = None;
+		return;
+	}
+
+	let binding = match slot {
+		BindingSlot::KeyboardMouse => keys.get_just_pressed().next().map(|key| Binding::Key(*key))
+			.or_else(|| mouse.get_just_pressed().next().map(|button| Binding::Mouse(*button))),
+		BindingSlot::Gamepad => gamepads.iter()
+			.find_map(|gamepad| gamepad.get_just_pressed().next().copied())
+			.map(Binding::GamepadButton),
+	};
+
+	let Some(binding) = binding else {
+		return;
+	};
+
+	// The new binding replaces the ones of the same device
+	let mut bindings: Vec<Binding> = map.get(action).iter()
+		.filter(|existing| existing.is_gamepad() != (slot == BindingSlot::Gamepad))
+		.copied()
+		.collect();
+	bindings.insert(0, binding);
+
+	settings.controls.bindings.insert(action, bindings);
+	capture.0 = None;
+}
+
+/// Stores the slider, toggle and dropdown values in the settings
+fn write_sliders(sliders: Query<(&Slider, &SliderSetting), Changed<Slider>>, mut settings: ResMut<Settings>) {
+	for (slider, setting) in sliders.iter() {
+		let value = setting.from_normalized(slider.value);
+
+		if (setting.get(&settings) - value).abs() > f32::EPSILON {
+			setting.set(&mut settings, value);
+		}
+	}
+}
+
+fn write_toggles(toggles: Query<(&Toggle, &ToggleSetting), Changed<Toggle>>, mut settings: ResMut<Settings>) {
+	for (toggle, setting) in toggles.iter() {
+		if setting.get(&settings) != toggle.on {
+			setting.set(&mut settings, toggle.on);
+		}
+	}
+}
+
+fn write_choices(dropdowns: Query<(&Dropdown, &ChoiceSetting), Changed<Dropdown>>, monitors: Monitors, mut settings: ResMut<Settings>) {
+	for (dropdown, setting) in dropdowns.iter() {
+		if setting.selected(&settings, &monitors) != dropdown.selected {
+			setting.select(&mut settings, &monitors, dropdown.selected);
+		}
+	}
+}
+
+/// Moves the widgets to the current settings, needed after a revert
+fn sync_widgets(
+	settings: Res<Settings>,
+	mut sliders: Query<(&mut Slider, &SliderSetting)>,
+	mut toggles: Query<(&mut Toggle, &ToggleSetting)>,
+	mut dropdowns: Query<(&mut Dropdown, &ChoiceSetting)>,
+	monitors: Monitors,
+	mut value_texts: Query<(&mut Text, &SliderValueText)>,
+) {
+	for (mut slider, setting) in sliders.iter_mut() {
+		let value = setting.normalized(&settings);
+
+		if (slider.value - value).abs() > 0.001 {
+			slider.value = value;
+		}
+	}
+
+	for (mut toggle, setting) in toggles.iter_mut() {
+		let on = setting.get(&settings);
+
+		if toggle.on != on {
+			toggle.on = on;
+		}
+	}
+
+	for (mut dropdown, setting) in dropdowns.iter_mut() {
+		let options = setting.options(&settings, &monitors);
+		let selected = setting.selected(&settings, &monitors);
+
+		if dropdown.options != options || dropdown.selected != selected {
+			dropdown.options = options;
+			dropdown.selected = selected;
+		}
+	}
+
+	for (mut text, value_text) in value_texts.iter_mut() {
+		text.0 = value_text.0.format(value_text.0.get(&settings));
+	}
+}
+
+/// Shows the bindings of every action, or a prompt on the one being rebound
+fn update_binding_text(
+	map: Res<InputMap>,
+	capture: Res<RebindCapture>,
+	buttons: Query<(&SettingsButton, &Children)>,
+	mut texts: Query<&mut Text>,
+) {
+	for (button, children) in buttons.iter() {
+		let SettingsButton::Rebind(action, slot) = *button else {
+			continue;
+		};
+
+		let content = if capture.0 == Some((action, slot)) {
+			"Press a button...".to_string()
+		} else {
+			map.get(action).iter()
+				.find(|binding| binding.is_gamepad() == (slot == BindingSlot::Gamepad))
+				.map_or("-".to_string(), Binding::name)
+		};
+
+		for child in children.iter() {
+			if let Ok(mut text) = texts.get_mut(*child) {
+				if text.0 != content {
+					text.0 = content.clone();
+				}
+			}
+		}
+	}
+}
+
+/// Highlights the selected tab
+fn update_tab_text(
+	tab: Res<SettingsTab>,
+	buttons: Query<(&SettingsButton, &Children)>,
+	mut colors: Query<&mut TextColor>,
+) {
+	for (button, children) in buttons.iter() {
+		let SettingsButton::Tab(button_tab) = *button else {
+			continue;
+		};
+
+		let color = if button_tab == *tab { TEXT_SELECTED_COLOR } else { TEXT_COLOR };
+
+		for child in children.iter() {
+			if let Ok(mut text_color) = colors.get_mut(*child) {
+				if text_color.0 != color {
+					text_color.0 = color;
+				}
+			}
+		}
+	}
+}
+
+fn update_unapplied_text(settings: Res<Settings>, preview: Res<SettingsPreview>, mut texts: Query<&mut Text, With<UnappliedText>>) {
+	let content = if preview.has_changes(&settings) { "Unapplied changes" } else { "" };
+
+	for mut text in texts.iter_mut() {
+		if text.0 != content {
+			text.0 = content.to_string();
+		}
+	}
+}
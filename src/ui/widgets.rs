@@ -0,0 +1,258 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use super::{label, spawn_button, BUTTON_COLOR, BUTTON_PRESSED_COLOR};
+
+/// Sliders, toggles and dropdowns, screens read the widget components to know what the player picked
+pub struct WidgetsPlugin;
+
+impl Plugin for WidgetsPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Update, (
+			(drag_sliders, update_slider_fill).chain(),
+			(click_toggles, update_toggle_text).chain(),
+			(click_dropdown_headers, click_dropdown_options, update_dropdowns).chain(),
+		));
+	}
+}
+
+/// A horizontal slider, `value` goes from 0 to 1
+#[derive(Component)]
+pub struct Slider {
+	pub value: f32,
+}
+
+/// A button switching between on and off
+#[derive(Component)]
+pub struct Toggle {
+	pub on: bool,
+}
+
+/// A button that opens a list of options
+#[derive(Component)]
+pub struct Dropdown {
+	pub options: Vec<String>,
+	pub selected: usize,
+	pub open: bool,
+	/// The options the list was built with
+	listed: Vec<String>,
+}
+
+#[derive(Component)]
+struct SliderFill;
+
+#[derive(Component)]
+struct DropdownHeader;
+
+#[derive(Component)]
+struct DropdownList;
+
+#[derive(Component)]
+struct DropdownOption {
+	dropdown: Entity,
+	index: usize,
+}
+
+/// Spawns a slider under a parent
+pub fn spawn_slider(parent: &mut ChildBuilder, value: f32, marker: impl Bundle) -> Entity {
+	parent.spawn((
+		Button,
+		RelativeCursorPosition::default(),
+		Node {
+			width: Val::Px(260.),
+			height: Val::Px(16.),
+			..default()
+		},
+		BackgroundColor(BUTTON_COLOR),
+		Slider { value },
+		marker,
+	)).with_children(|track| {
+		track.spawn((
+			SliderFill,
+			Node {
+				width: Val::Percent(value * 100.),
+				height: Val::Percent(100.),
+				..default()
+			},
+			BackgroundColor(BUTTON_PRESSED_COLOR),
+		));
+	}).id()
+}
+
+/// Spawns an on and off button under a parent
+pub fn spawn_toggle(parent: &mut ChildBuilder, on: bool, marker: impl Bundle) -> Entity {
+	spawn_button(parent, toggle_text(on), (Toggle { on }, marker))
+}
+
+/// Spawns a dropdown under a parent
+pub fn spawn_dropdown(parent: &mut ChildBuilder, options: Vec<String>, selected: usize, marker: impl Bundle) -> Entity {
+	let header_text = options.get(selected).cloned().unwrap_or_default();
+
+	parent.spawn((
+		Node {
+			flex_direction: FlexDirection::Column,
+			..default()
+		},
+		marker,
+	)).with_children(|dropdown| {
+		let entity = dropdown.parent_entity();
+
+		spawn_button(dropdown, &header_text, DropdownHeader);
+
+		dropdown.spawn((
+			DropdownList,
+			Node {
+				display: Display::None,
+				position_type: PositionType::Absolute,
+				top: Val::Percent(100.),
+				flex_direction: FlexDirection::Column,
+				..default()
+			},
+			GlobalZIndex(10),
+		)).with_children(|list| spawn_options(list, entity, &options));
+	}).insert(Dropdown { listed: options.clone(), options, selected, open: false }).id()
+}
+
+fn spawn_options(list: &mut ChildBuilder, dropdown: Entity, options: &[String]) {
+	for (index, option) in options.iter().enumerate() {
+		list.spawn((
+			Button,
+			Node {
+				width: Val::Px(260.),
+				height: Val::Px(28.),
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				..default()
+			},
+			BackgroundColor(BUTTON_COLOR),
+			DropdownOption { dropdown, index },
+		)).with_children(|button| {
+			button.spawn(label(option));
+		});
+	}
+}
+
+fn toggle_text(on: bool) -> &'static str {
+	if on { "On" } else { "Off" }
+}
+
+/// Moves sliders to the cursor while they are held
+fn drag_sliders(mut sliders: Query<(&Interaction, &RelativeCursorPosition, &mut Slider)>) {
+	for (interaction, cursor, mut slider) in sliders.iter_mut() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		if let Some(position) = cursor.normalized {
+			let value = position.x.clamp(0.0, 1.0);
+
+			if (slider.value - value).abs() > f32::EPSILON {
+				slider.value = value;
+			}
+		}
+	}
+}
+
+fn update_slider_fill(sliders: Query<(&Slider, &Children), Changed<Slider>>, mut fills: Query<&mut Node, With<SliderFill>>) {
+	for (slider, children) in sliders.iter() {
+		for child in children.iter() {
+			if let Ok(mut node) = fills.get_mut(*child) {
+				node.width = Val::Percent(slider.value.clamp(0.0, 1.0) * 100.);
+			}
+		}
+	}
+}
+
+fn click_toggles(mut toggles: Query<(&Interaction, &mut Toggle), Changed<Interaction>>) {
+	for (interaction, mut toggle) in toggles.iter_mut() {
+		if *interaction == Interaction::Pressed {
+			toggle.on = !toggle.on;
+		}
+	}
+}
+
+fn update_toggle_text(toggles: Query<(&Toggle, &Children), Changed<Toggle>>, mut texts: Query<&mut Text>) {
+	for (toggle, children) in toggles.iter() {
+		for child in children.iter() {
+			if let Ok(mut text) = texts.get_mut(*child) {
+				text.0 = toggle_text(toggle.on).to_string();
+			}
+		}
+	}
+}
+
+fn click_dropdown_headers(headers: Query<(&Interaction, &Parent), (Changed<Interaction>, With<DropdownHeader>)>, mut dropdowns: Query<&mut Dropdown>) {
+	for (interaction, parent) in headers.iter() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		if let Ok(mut dropdown) = dropdowns.get_mut(parent.get()) {
+			dropdown.open = !dropdown.open;
+		}
+	}
+}
+
+fn click_dropdown_options(options: Query<(&Interaction, &DropdownOption), Changed<Interaction>>, mut dropdowns: Query<&mut Dropdown>) {
+	for (interaction, option) in options.iter() {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		if let Ok(mut dropdown) = dropdowns.get_mut(option.dropdown) {
+			dropdown.selected = option.index;
+			dropdown.open = false;
+		}
+	}
+}
+
+/// Shows the selected option in the header, rebuilds the option list when the options change and opens or closes it
+fn update_dropdowns(
+	mut commands: Commands,
+	mut dropdowns: Query<(Entity, &mut Dropdown, &Children), Changed<Dropdown>>,
+	headers: Query<&Children, With<DropdownHeader>>,
+	mut lists: Query<&mut Node, With<DropdownList>>,
+	mut texts: Query<&mut Text>,
+) {
+	for (entity, mut dropdown, children) in dropdowns.iter_mut() {
+		let rebuild = dropdown.listed != dropdown.options;
+		if rebuild {
+			dropdown.listed = dropdown.options.clone();
+		}
+
+		for child in children.iter() {
+			if let Ok(header_children) = headers.get(*child) {
+				for header_child in header_children.iter() {
+					if let Ok(mut text) = texts.get_mut(*header_child) {
+						text.0 = dropdown.options.get(dropdown.selected).cloned().unwrap_or_default();
+					}
+				}
+			}
+
+			if let Ok(mut node) = lists.get_mut(*child) {
+				node.display = if dropdown.open { Display::Flex } else { Display::None };
+
+				if rebuild {
+					commands.entity(*child).despawn_descendants()
+						.with_children(|list| spawn_options(list, entity, &dropdown.options));
+				}
+			}
+		}
+	}
+}
+
+/// A row with a label on the left and a widget on the right
+pub fn spawn_row(parent: &mut ChildBuilder, text: &str, widget: impl FnOnce(&mut ChildBuilder)) {
+	parent.spawn(Node {
+		width: Val::Percent(100.),
+		justify_content: JustifyContent::SpaceBetween,
+		align_items: AlignItems::Center,
+		column_gap: Val::Px(12.),
+		..default()
+	}).with_children(|row| {
+		row.spawn(label(text));
+		row.spawn(Node {
+			align_items: AlignItems::Center,
+			column_gap: Val::Px(12.),
+			..default()
+		}).with_children(widget);
+	});
+}
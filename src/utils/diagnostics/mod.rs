@@ -2,6 +2,7 @@ use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*, text::FontSmoothi
 
 mod fps;
 mod pos;
+mod stance;
 
 const FONT_SIZE: f32 = 18.0;
 const FONT_COLOR: Color = Color::WHITE;
@@ -14,7 +15,7 @@ impl Plugin for DebugMenuPlugin {
 		app.add_plugins(FrameTimeDiagnosticsPlugin::default())
 		.init_resource::<DiagsState>()
 		.add_systems(Startup, spawn_text)
-		.add_systems(Update, (fps::update, pos::update));
+		.add_systems(Update, (fps::update, pos::update, stance::update));
 	}
 }
 
@@ -24,6 +25,7 @@ pub struct DiagsState {
 	/// Public, to allow flexible use, but in general use the methods to interact.
 	pub fps_timer: Timer,
 	pub pos_timer: Timer,
+	pub stance_timer: Timer,
 	/// A flag to indicate to update the display, even if the timer has not popped.
 	/// Public, to allow flexible use, but in general use the methods to interact.
 	pub update_now: bool,
@@ -34,6 +36,7 @@ impl Default for DiagsState {
 		Self { 
 			fps_timer: Timer::new(UPDATE_INTERVAL, TimerMode::Repeating), 
 			pos_timer: Timer::new(UPDATE_INTERVAL, TimerMode::Repeating),
+			stance_timer: Timer::new(UPDATE_INTERVAL, TimerMode::Repeating),
 			update_now: false 
 		}
 	}
@@ -44,6 +47,7 @@ impl DiagsState {
 	pub fn enable(&mut self) {
 		self.fps_timer.unpause();
 		self.pos_timer.unpause();
+		self.stance_timer.unpause();
 		self.update_now = true;
 	}
 
@@ -51,6 +55,7 @@ impl DiagsState {
 	pub fn disable(&mut self) {
 		self.fps_timer.pause();
 		self.pos_timer.pause();
+		self.stance_timer.pause();
 		self.update_now = true;
 	}
 
@@ -90,8 +95,15 @@ fn spawn_text(mut commands: Commands, asset_server: Res<AssetServer>) {
 		TextColor(Color::from(FONT_COLOR))
 	);
 
+	let stance_section = (
+		Text::new(stance::STANCE_INITIAL.to_string()),
+		TextFont {font: font.clone().into(), font_size: FONT_SIZE, font_smoothing: FontSmoothing::AntiAliased, ..Default::default()},
+		TextColor(Color::from(FONT_COLOR))
+	);
+
 	let fps_id = commands.spawn(fps_section).insert(fps::DiagsFPS).id();
 	let pos_id = commands.spawn(position_section).insert(pos::DiagsPos).id();
+	let stance_id = commands.spawn(stance_section).insert(stance::DiagsStance).id();
 
-	commands.entity(column_id).add_child(fps_id).add_child(pos_id);
+	commands.entity(column_id).add_child(fps_id).add_child(pos_id).add_child(stance_id);
 }
\ No newline at end of file
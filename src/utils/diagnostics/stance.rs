@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use crate::player::controller::PlayerControllerState;
+use super::DiagsState;
+
+pub const STANCE_INITIAL: &str = "Stance: ...";
+
+#[derive(Component)]
+pub struct DiagsStance;
+
+pub fn update(time: Res<Time<Real>>, player: Res<PlayerControllerState>, state_resource: Option<ResMut<DiagsState>>, mut text_query: Query<&mut Text, With<DiagsStance>>) {
+	let mut state = match state_resource {
+		None => {
+			bevy::log::error!("DiagsStance state resource not found"); 
+			return;
+		},
+		Some(state) => state,
+	};
+
+	if state.update_now || state.stance_timer.tick(time.delta()).just_finished() {
+		for mut text in text_query.iter_mut() {
+			let value = &mut text.0;
+			value.clear();
+
+			// Time is paused so leave the text empty
+			if !state.stance_timer.paused() {
+				value.push_str("Stance: ");
+				value.push_str(player.stance.name());
+			}
+		}
+	}
+}
@@ -1,63 +1,149 @@
-use bevy::{prelude::*, window::{CursorGrabMode, PrimaryWindow}};
+use bevy::{prelude::*, window::{CursorGrabMode, PrimaryWindow, WindowFocused, WindowResized}};
+use crate::state::GameState;
 
 pub struct CursorPlugin;
 
 impl Plugin for CursorPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Cursor>()
-            .add_systems(Update, update_cursor_locking)
-            .add_systems(Update, lock_cursor_position)
-            .add_systems(Startup, init_cursor_properties);
+            .add_event::<CursorLockChanged>()
+            .add_systems(OnEnter(GameState::InGame), capture_cursor)
+            .add_systems(OnExit(GameState::InGame), release_cursor)
+            .add_systems(Update, (
+                release_on_focus_loss,
+                lock_on_click.run_if(in_state(GameState::InGame)),
+                recenter_on_resize,
+            ).chain());
     }
 }
 
+/// Whether the cursor is captured by the game, it is only wanted while playing and released whenever the window loses focus
 #[derive(Resource, Default)]
 pub struct Cursor {
     locked: bool,
+    wanted: bool,
+}
+
+/// Sent whenever the cursor is locked or released
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CursorLockChanged {
+    pub locked: bool,
 }
 
 impl Cursor {
-    /// This inverts the cursor lock
-    pub fn invert_lock(&mut self, window: &mut Mut<'_, Window>) {
-        self.locked = !self.locked;
-        window.cursor_options.visible = !self.locked;
+    /// Mouse movement should only turn the camera while this is true
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// This locks or releases the cursor
+    fn set_locked(&mut self, window: &mut Window, locked: bool, events: &mut EventWriter<CursorLockChanged>) {
+        if self.locked == locked {
+            return;
+        }
+
+        self.locked = locked;
+        window.cursor_options.visible = !locked;
+        window.cursor_options.grab_mode = if locked { CursorGrabMode::Locked } else { CursorGrabMode::None };
+        center_cursor(window);
+
+        events.send(CursorLockChanged { locked });
+    }
 
+    /// Marks the cursor as released when the window is gone
+    fn forget_window(&mut self, events: &mut EventWriter<CursorLockChanged>) {
         if self.locked {
-            let window_width = window.width();
-            let window_height = window.height();
-            window.cursor_options.grab_mode = CursorGrabMode::Locked;
-            window.set_cursor_position(Some(Vec2::new(window_width / 2., window_height / 2.)));
-        } else {
-            window.cursor_options.grab_mode = CursorGrabMode::None;
+            self.locked = false;
+            events.send(CursorLockChanged { locked: false });
         }
     }
 }
 
-/// This locks the cursor to the center of the window
-fn lock_cursor_position(mut window_query: Query<&mut Window, With<PrimaryWindow>>, cursor: ResMut<Cursor>) {
-    let mut window = window_query.get_single_mut().unwrap();
+fn center_cursor(window: &mut Window) {
+    let center = Vec2::new(window.width() / 2., window.height() / 2.);
+    window.set_cursor_position(Some(center));
+}
+
+/// The cursor is captured while playing
+fn capture_cursor(mut window_query: Query<&mut Window, With<PrimaryWindow>>, mut cursor: ResMut<Cursor>, mut events: EventWriter<CursorLockChanged>) {
+    cursor.wanted = true;
 
-    if !cursor.locked {
+    let Ok(mut window) = window_query.get_single_mut() else {
+        cursor.forget_window(&mut events);
         return;
+    };
+
+    if window.focused {
+        cursor.set_locked(&mut window, true, &mut events);
+    }
+}
+
+/// The cursor is released in menus
+fn release_cursor(mut window_query: Query<&mut Window, With<PrimaryWindow>>, mut cursor: ResMut<Cursor>, mut events: EventWriter<CursorLockChanged>) {
+    cursor.wanted = false;
+
+    match window_query.get_single_mut() {
+        Ok(mut window) => cursor.set_locked(&mut window, false, &mut events),
+        Err(_) => cursor.forget_window(&mut events),
     }
+}
+
+/// Gives the mouse back to the system when alt tabbing away
+fn release_on_focus_loss(
+    mut focus_events: EventReader<WindowFocused>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut cursor: ResMut<Cursor>,
+    mut events: EventWriter<CursorLockChanged>,
+) {
+    for focus in focus_events.read() {
+        if focus.focused {
+            continue;
+        }
 
-    let window_width = window.width();
-    let window_height = window.height();
-    window.set_cursor_position(Some(Vec2::new(window_width / 2., window_height / 2.)));
+        match window_query.get_mut(focus.window) {
+            Ok(mut window) => cursor.set_locked(&mut window, false, &mut events),
+            Err(_) => {
+                if window_query.is_empty() {
+                    cursor.forget_window(&mut events);
+                }
+            }
+        }
+    }
 }
 
-/// This initializes the cursor properties
-fn init_cursor_properties(mut window_query: Query<&mut Window, With<PrimaryWindow>>, mut cursor: ResMut<Cursor>) {
-    let mut window = window_query.get_single_mut().unwrap();
+/// Takes the cursor again when the player clicks back into the game
+fn lock_on_click(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut cursor: ResMut<Cursor>,
+    mut events: EventWriter<CursorLockChanged>,
+) {
+    if !cursor.wanted || cursor.locked || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
 
-    cursor.invert_lock(&mut window);
+    if window.focused {
+        cursor.set_locked(&mut window, true, &mut events);
+    }
 }
 
-/// This updates the cursor locking
-fn update_cursor_locking(keys: Res<ButtonInput<KeyCode>>, mut window_query: Query<&mut Window, With<PrimaryWindow>>, mut cursor: ResMut<Cursor>) {
-    let mut window = window_query.get_single_mut().unwrap();
+/// Keeps a locked cursor in the middle of the window when its size changes
+fn recenter_on_resize(
+    mut resize_events: EventReader<WindowResized>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    cursor: Res<Cursor>,
+) {
+    for resize in resize_events.read() {
+        if !cursor.locked {
+            continue;
+        }
 
-    if keys.just_pressed(KeyCode::Escape) {
-        cursor.invert_lock(&mut window);
+        if let Ok(mut window) = window_query.get_mut(resize.window) {
+            center_cursor(&mut window);
+        }
     }
-}
\ No newline at end of file
+}
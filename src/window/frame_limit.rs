@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+use bevy::prelude::*;
+use crate::settings::Settings;
+
+/// OS sleeps can overshoot by about this much, the rest of the wait is spun
+const SPIN_MARGIN: Duration = Duration::from_micros(1500);
+
+/// Waits at the end of every frame to stay under the frame rate cap from the settings
+pub struct FrameLimitPlugin;
+
+impl Plugin for FrameLimitPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Last, limit_frame_rate);
+    }
+}
+
+/// Paces frames against a running deadline so a frame that runs or sleeps long is made up by the next one
+fn limit_frame_rate(settings: Res<Settings>, mut deadline: Local<Option<Instant>>) {
+    let max_fps = settings.display.max_fps;
+
+    if max_fps == 0 {
+        *deadline = None;
+        return;
+    }
+
+    let frame_time = Duration::from_secs_f64(1.0 / max_fps as f64);
+    let now = Instant::now();
+
+    let Some(target) = *deadline else {
+        *deadline = Some(now + frame_time);
+        return;
+    };
+
+    // Sleep most of the wait and spin the end of it, sleeping alone is only as precise as the OS timer
+    if let Some(remaining) = target.checked_duration_since(now) {
+        if remaining > SPIN_MARGIN {
+            std::thread::sleep(remaining - SPIN_MARGIN);
+        }
+
+        while Instant::now() < target {
+            std::hint::spin_loop();
+        }
+    }
+
+    // A frame that fell more than a whole frame behind starts over instead of rushing to catch up
+    let next = target + frame_time;
+    *deadline = Some(if next < Instant::now() { Instant::now() + frame_time } else { next });
+}
@@ -1,22 +1,151 @@
-use bevy::{prelude::*, window::{PrimaryWindow, WindowMode, WindowResolution}};
+use bevy::{ecs::system::SystemParam, prelude::*, window::{Monitor, MonitorSelection, PrimaryMonitor, PrimaryWindow, WindowMode, WindowPosition}};
+use crate::settings::{DisplayMode, DisplaySettings, Settings};
 
 mod cursor;
+mod frame_limit;
+
+pub use cursor::{Cursor, CursorLockChanged};
+
+/// Resolutions smaller than this aren't offered
+const MIN_RESOLUTION: UVec2 = UVec2::new(1280, 720);
+
+/// Offered when the monitor doesn't report its video modes
+const FALLBACK_RESOLUTIONS: [(f32, f32); 6] = [
+    (1280., 720.),
+    (1366., 768.),
+    (1600., 900.),
+    (1920., 1080.),
+    (2560., 1440.),
+    (3840., 2160.),
+];
 
 pub struct WindowSettingsPlugin;
 
 impl Plugin for WindowSettingsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(cursor::CursorPlugin)
-            .add_systems(PreStartup, init_window);
+        app.add_plugins((cursor::CursorPlugin, frame_limit::FrameLimitPlugin))
+            .add_systems(PreStartup, init_window)
+            .add_systems(Update, apply_display_settings.run_if(resource_changed::<Settings>.or(monitors_added)));
+    }
+}
+
+/// The connected monitors
+#[derive(SystemParam)]
+pub struct Monitors<'w, 's> {
+    query: Query<'w, 's, (Entity, &'static Monitor, Has<PrimaryMonitor>)>,
+}
+
+impl Monitors<'_, '_> {
+    /// Names shown in menus and stored in the settings
+    pub fn names(&self) -> Vec<String> {
+        self.query.iter().enumerate().map(|(index, (_, monitor, _))| monitor_name(index, monitor)).collect()
     }
+
+    /// Index of the monitor picked in the display settings in `names`
+    pub fn selected_index(&self, display: &DisplaySettings) -> Option<usize> {
+        let names = self.names();
+
+        display.monitor.as_ref()
+            .and_then(|name| names.iter().position(|other| other == name))
+            .or_else(|| self.query.iter().position(|(_, _, primary)| primary))
+    }
+
+    /// The monitor picked in the display settings, or the primary monitor
+    pub fn selected(&self, display: &DisplaySettings) -> Option<(Entity, &Monitor)> {
+        let index = self.selected_index(display)?;
+        self.query.iter().nth(index).map(|(entity, monitor, _)| (entity, monitor))
+    }
+
+    /// Resolutions supported by the selected monitor from the largest, always including the one in the settings
+    pub fn resolutions(&self, display: &DisplaySettings) -> Vec<(f32, f32)> {
+        let mut sizes: Vec<UVec2> = self.selected(display)
+            .map(|(_, monitor)| monitor.video_modes.iter()
+                .map(|mode| mode.physical_size)
+                .filter(|size| size.cmpge(MIN_RESOLUTION).all())
+                .collect())
+            .unwrap_or_default();
+        sizes.sort_by(|a, b| b.x.cmp(&a.x).then(b.y.cmp(&a.y)));
+        sizes.dedup();
+
+        let mut resolutions: Vec<(f32, f32)> = if sizes.is_empty() {
+            FALLBACK_RESOLUTIONS.to_vec()
+        } else {
+            sizes.iter().map(|size| (size.x as f32, size.y as f32)).collect()
+        };
+
+        let current = (display.width, display.height);
+        if !resolutions.contains(&current) {
+            resolutions.push(current);
+        }
+
+        resolutions
+    }
+}
+
+fn monitor_name(index: usize, monitor: &Monitor) -> String {
+    monitor.name.clone().unwrap_or_else(|| format!("Monitor {}", index + 1))
+}
+
+/// Monitors show up after startup and can be plugged in later
+fn monitors_added(monitors: Query<(), Added<Monitor>>) -> bool {
+    !monitors.is_empty()
 }
 
-fn init_window(mut window_query: Query<&mut Window, With<PrimaryWindow>>) {
+fn init_window(mut window_query: Query<&mut Window, With<PrimaryWindow>>, settings: Res<Settings>) {
     if let Ok(mut window) = window_query.get_single_mut() {
         window.title = "Turning Dawn".to_string();
-        window.resolution = WindowResolution::new(1920., 1080.);
+        // The settings are in physical pixels, a logical size would be scaled up on HiDPI monitors
+        window.resolution.set_physical_resolution(settings.display.width.max(1.0) as u32, settings.display.height.max(1.0) as u32);
+        window.present_mode = settings.display.present_mode;
         window.mode = WindowMode::Windowed;
-        //window.resolution = WindowResolution::new(2560., 1440.);
-        //window.mode = WindowMode::BorderlessFullscreen(MonitorSelection::Primary);
     }
-}
\ No newline at end of file
+}
+
+/// Applies the display settings to the primary window when they change
+fn apply_display_settings(
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    monitors: Monitors,
+    settings: Res<Settings>,
+    mut applied: Local<Option<(DisplaySettings, Option<Entity>)>>,
+) {
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+
+    let display = &settings.display;
+    let monitor = monitors.selected(display).map(|(entity, _)| entity);
+
+    // Other settings changing shouldn't move the window
+    if applied.as_ref().is_some_and(|(applied_display, applied_monitor)| applied_display == display && *applied_monitor == monitor) {
+        return;
+    }
+    *applied = Some((display.clone(), monitor));
+
+    let selection = monitor.map_or(MonitorSelection::Primary, MonitorSelection::Entity);
+    let mode = match display.mode {
+        DisplayMode::Windowed => WindowMode::Windowed,
+        DisplayMode::Borderless => WindowMode::BorderlessFullscreen(selection),
+        DisplayMode::Fullscreen => WindowMode::SizedFullscreen(selection),
+    };
+
+    if display.mode != DisplayMode::Borderless {
+        let width = display.width.max(1.0) as u32;
+        let height = display.height.max(1.0) as u32;
+
+        if window.resolution.physical_width() != width || window.resolution.physical_height() != height {
+            window.resolution.set_physical_resolution(width, height);
+        }
+    }
+
+    if display.mode == DisplayMode::Windowed {
+        window.position = WindowPosition::Centered(selection);
+    }
+
+    if window.mode != mode {
+        window.mode = mode;
+    }
+
+    if window.present_mode != display.present_mode {
+        window.present_mode = display.present_mode;
+    }
+}